@@ -0,0 +1,336 @@
+// Packs many small images into a few texture pages so sprites can share
+// one bound texture. Pages are filled shelf by shelf: rows of sprites of
+// similar height, each new shelf opened below the last.
+use super::texture::{Filter, Sampling, Texture, TextureBuilder, Wrap};
+use super::{Error, GL};
+use image::RgbaImage;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Texture coordinates of a sprite. Rows are stored as the image gives
+// them, like Texture::from_path without flip_y, so `min.1` is the first
+// row of the image.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct UvRect {
+    pub min: (f32, f32),
+    pub max: (f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasSprite {
+    // index into TextureAtlas::pages
+    pub page: usize,
+    // (x, y, width, height) in pixels, without padding
+    pub rect: (u32, u32, u32, u32),
+    pub uv: UvRect,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    // where the next sprite on the shelf starts
+    x: u32,
+}
+
+#[derive(Debug)]
+struct Page {
+    texture: Texture,
+    shelves: Vec<Shelf>,
+}
+
+impl Page {
+    // Lowest (x, y) corner of a free (width, height) area, picking the shelf
+    // that wastes the least height
+    fn allocate(
+        &mut self,
+        (width, height): (u32, u32),
+        (page_width, page_height): (u32, u32),
+    ) -> Option<(u32, u32)> {
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| {
+                shelf.height >= height && page_width - shelf.x >= width
+            })
+            .min_by_key(|shelf| shelf.height - height);
+        if let Some(shelf) = best {
+            let x = shelf.x;
+            shelf.x += width;
+            return Some((x, shelf.y));
+        }
+        let y = self.shelves.last().map_or(0, |last| last.y + last.height);
+        if page_height - y < height {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y,
+            height,
+            x: width,
+        });
+        Some((0, y))
+    }
+}
+
+// Sprites are RGBA8 and can be added at any time. A sprite that fits no
+// page opens a new one.
+#[derive(Debug)]
+pub struct TextureAtlas<K> {
+    page_size: (u32, u32),
+    padding: u32,
+    sampling: Sampling,
+    pages: Vec<Page>,
+    sprites: HashMap<K, AtlasSprite>,
+}
+
+impl<K: Eq + Hash> TextureAtlas<K> {
+    // `padding` pixels around each sprite repeat its edge so linear
+    // filtering doesn't pick up the neighbours
+    pub fn new((width, height): (u32, u32), padding: u32) -> TextureAtlas<K> {
+        TextureAtlas {
+            page_size: (width, height),
+            padding,
+            sampling: Sampling::new(Filter::Linear, Wrap::ClampToEdge),
+            pages: Vec::new(),
+            sprites: HashMap::new(),
+        }
+    }
+
+    // Used for pages created from now on
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    // Adding an id again points it to the new image, the space of the old
+    // one is not reused
+    pub fn add(
+        &mut self,
+        gl: &GL,
+        id: K,
+        image: &RgbaImage,
+    ) -> Result<AtlasSprite, Error> {
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return Err(Error::Image("sprite image is empty".to_string()));
+        }
+        let border = self.padding.checked_mul(2);
+        let padded = border.and_then(|border| {
+            Some((width.checked_add(border)?, height.checked_add(border)?))
+        });
+        let padded = match padded {
+            Some((w, h)) if w <= self.page_size.0 && h <= self.page_size.1 => {
+                (w, h)
+            }
+            _ => {
+                let border = self.padding.saturating_mul(2);
+                return Err(Error::OutOfBounds {
+                    region: (
+                        0,
+                        0,
+                        width.saturating_add(border),
+                        height.saturating_add(border),
+                    ),
+                    size: self.page_size,
+                });
+            }
+        };
+        let page_size = self.page_size;
+        let found = self.pages.iter_mut().enumerate().find_map(|(i, page)| {
+            page.allocate(padded, page_size).map(|corner| (i, corner))
+        });
+        let (page, (x, y)) = match found {
+            Some(found) => found,
+            None => {
+                self.add_page(gl)?;
+                let page = self.pages.len() - 1;
+                let corner = self.pages[page]
+                    .allocate(padded, page_size)
+                    .expect("an empty page fits the sprite");
+                (page, corner)
+            }
+        };
+        self.pages[page].texture.write_region(
+            x,
+            y,
+            padded.0,
+            padded.1,
+            &extrude(image, self.padding),
+        )?;
+        let (x, y) = (x + self.padding, y + self.padding);
+        let (page_width, page_height) =
+            (page_size.0 as f32, page_size.1 as f32);
+        let sprite = AtlasSprite {
+            page,
+            rect: (x, y, width, height),
+            uv: UvRect {
+                min: (x as f32 / page_width, y as f32 / page_height),
+                max: (
+                    (x + width) as f32 / page_width,
+                    (y + height) as f32 / page_height,
+                ),
+            },
+        };
+        self.sprites.insert(id, sprite);
+        Ok(sprite)
+    }
+
+    pub fn get<Q>(&self, id: &Q) -> Option<AtlasSprite>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.sprites.get(id).copied()
+    }
+
+    // The page texture a sprite is on, to bind together with its uv
+    pub fn texture<Q>(&self, id: &Q) -> Option<&Texture>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get(id).map(|sprite| &self.pages[sprite.page].texture)
+    }
+
+    pub fn pages(&self) -> impl Iterator<Item = &Texture> {
+        self.pages.iter().map(|page| &page.texture)
+    }
+
+    pub fn page_size(&self) -> (u32, u32) {
+        self.page_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    fn add_page(&mut self, gl: &GL) -> Result<(), Error> {
+        let (width, height) = self.page_size;
+        // cleared so padding and free space sample as transparent
+        let clear = vec![0; width as usize * height as usize * 4];
+        let texture = TextureBuilder::new(width, height)
+            .sampling(self.sampling)
+            .build(gl, Some(&clear))?;
+        self.pages.push(Page {
+            texture,
+            shelves: Vec::new(),
+        });
+        Ok(())
+    }
+}
+
+// `image` with its edge pixels repeated `padding` times on each side
+fn extrude(image: &RgbaImage, padding: u32) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let clamp =
+        |value: u32, len: u32| value.saturating_sub(padding).min(len - 1);
+    let mut bytes = Vec::with_capacity(
+        (width + 2 * padding) as usize * (height + 2 * padding) as usize * 4,
+    );
+    for y in 0..height + 2 * padding {
+        for x in 0..width + 2 * padding {
+            let pixel = image.get_pixel(clamp(x, width), clamp(y, height));
+            bytes.extend_from_slice(&pixel.data);
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+    use image::Rgba;
+
+    fn page(gl: &GL) -> Page {
+        Page {
+            texture: TextureBuilder::new(8, 8).build(gl, None).unwrap(),
+            shelves: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn allocate_picks_the_tightest_shelf() {
+        let gl = GL::from_backend(Recorder::new());
+        let mut page = page(&gl);
+        let size = (8, 8);
+        assert_eq!(page.allocate((4, 2), size), Some((0, 0)));
+        assert_eq!(page.allocate((4, 4), size), Some((0, 2)));
+        // both shelves fit, the first one wastes no height
+        assert_eq!(page.allocate((2, 2), size), Some((4, 0)));
+        assert_eq!(page.allocate((2, 1), size), Some((6, 0)));
+        // the first shelf is full
+        assert_eq!(page.allocate((2, 2), size), Some((4, 2)));
+        assert_eq!(page.allocate((2, 3), size), Some((6, 2)));
+        assert_eq!(page.allocate((8, 2), size), Some((0, 6)));
+        // every shelf is full and no height is left for a new one
+        assert_eq!(page.allocate((1, 1), size), None);
+    }
+
+    #[test]
+    fn full_pages_open_a_new_one() {
+        let gl = GL::from_backend(Recorder::new());
+        let mut atlas = TextureAtlas::new((8, 8), 0);
+        let image = RgbaImage::new(8, 5);
+        assert_eq!(atlas.add(&gl, 0, &image).unwrap().page, 0);
+        assert_eq!(atlas.add(&gl, 1, &image).unwrap().page, 1);
+        let small = RgbaImage::new(8, 3);
+        let sprite = atlas.add(&gl, 2, &small).unwrap();
+        assert_eq!((sprite.page, sprite.rect), (0, (0, 5, 8, 3)));
+        assert_eq!(atlas.pages().count(), 2);
+        assert_eq!(atlas.len(), 3);
+    }
+
+    #[test]
+    fn sprites_larger_than_a_page_are_rejected() {
+        let gl = GL::from_backend(Recorder::new());
+        let mut atlas = TextureAtlas::new((8, 8), 1);
+        assert!(matches!(
+            atlas.add(&gl, 0, &RgbaImage::new(7, 2)),
+            Err(Error::OutOfBounds {
+                region: (0, 0, 9, 4),
+                size: (8, 8),
+            })
+        ));
+        let mut atlas = TextureAtlas::new((8, 8), u32::MAX / 2 + 1);
+        assert!(matches!(
+            atlas.add(&gl, 0, &RgbaImage::new(1, 1)),
+            Err(Error::OutOfBounds { .. })
+        ));
+        assert_eq!(atlas.pages().count(), 0);
+    }
+
+    #[test]
+    fn sprite_uv_excludes_padding() {
+        let gl = GL::from_backend(Recorder::new());
+        let mut atlas = TextureAtlas::new((8, 8), 1);
+        let sprite = atlas.add(&gl, "a", &RgbaImage::new(2, 2)).unwrap();
+        assert_eq!(sprite.rect, (1, 1, 2, 2));
+        assert_eq!(
+            sprite.uv,
+            UvRect {
+                min: (0.125, 0.125),
+                max: (0.375, 0.375),
+            }
+        );
+        assert_eq!(atlas.get("a"), Some(sprite));
+    }
+
+    #[test]
+    fn extrude_repeats_edge_pixels() {
+        let image = RgbaImage::from_fn(2, 2, |x, y| {
+            let value = (y * 2 + x + 1) as u8;
+            Rgba([value, 0, 0, 255])
+        });
+        let bytes = extrude(&image, 1);
+        // 4x4, the first and last rows and columns repeat the edge
+        let red: Vec<u8> = bytes.iter().step_by(4).copied().collect();
+        assert_eq!(red, vec![1, 1, 2, 2, 1, 1, 2, 2, 3, 3, 4, 4, 3, 3, 4, 4]);
+        assert_eq!(extrude(&image, 0), image.into_raw());
+    }
+}
@@ -0,0 +1,1060 @@
+use glow::{ActiveUniform, Context};
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+
+// Subset of glow::Context used by red. Handles are plain GL names, so the
+// trait stays object safe and GL can hold any implementation (see recorder).
+#[allow(clippy::missing_safety_doc, clippy::too_many_arguments)]
+pub trait Backend: std::fmt::Debug {
+    unsafe fn create_shader(&self, shader_type: u32) -> Result<c_uint, String>;
+    unsafe fn delete_shader(&self, shader: c_uint);
+    unsafe fn shader_source(&self, shader: c_uint, source: &str);
+    unsafe fn compile_shader(&self, shader: c_uint);
+    unsafe fn get_shader_compile_status(&self, shader: c_uint) -> bool;
+    unsafe fn get_shader_info_log(&self, shader: c_uint) -> String;
+    unsafe fn create_program(&self) -> Result<c_uint, String>;
+    unsafe fn delete_program(&self, program: c_uint);
+    unsafe fn attach_shader(&self, program: c_uint, shader: c_uint);
+    unsafe fn detach_shader(&self, program: c_uint, shader: c_uint);
+    unsafe fn bind_attrib_location(
+        &self,
+        program: c_uint,
+        index: u32,
+        name: &str,
+    );
+    unsafe fn link_program(&self, program: c_uint);
+    unsafe fn get_program_link_status(&self, program: c_uint) -> bool;
+    unsafe fn get_program_info_log(&self, program: c_uint) -> String;
+    unsafe fn get_active_uniforms(&self, program: c_uint) -> u32;
+    // None when the backend can't list attributes
+    unsafe fn get_active_attributes(&self, program: c_uint) -> Option<u32>;
+    unsafe fn get_active_attribute(
+        &self,
+        program: c_uint,
+        index: u32,
+    ) -> Option<ActiveAttribute>;
+    unsafe fn get_active_uniform(
+        &self,
+        program: c_uint,
+        index: u32,
+    ) -> Option<ActiveUniform>;
+    unsafe fn use_program(&self, program: Option<c_uint>);
+    unsafe fn get_uniform_location(
+        &self,
+        program: c_uint,
+        name: &str,
+    ) -> Option<c_uint>;
+    unsafe fn get_attrib_location(&self, program: c_uint, name: &str) -> i32;
+    unsafe fn create_buffer(&self) -> Result<c_uint, String>;
+    unsafe fn delete_buffer(&self, buffer: c_uint);
+    unsafe fn bind_buffer(&self, target: u32, buffer: Option<c_uint>);
+    unsafe fn buffer_data_u8_slice(&self, target: u32, data: &[u8], usage: u32);
+    unsafe fn map_buffer_range(
+        &self,
+        target: u32,
+        offset: i32,
+        length: i32,
+        access: u32,
+    ) -> *mut u8;
+    unsafe fn flush_mapped_buffer_range(
+        &self,
+        target: u32,
+        offset: i32,
+        length: i32,
+    );
+    unsafe fn unmap_buffer(&self, target: u32);
+    unsafe fn create_vertex_array(&self) -> Result<c_uint, String>;
+    unsafe fn delete_vertex_array(&self, vertex_array: c_uint);
+    unsafe fn bind_vertex_array(&self, vertex_array: Option<c_uint>);
+    unsafe fn enable_vertex_attrib_array(&self, index: u32);
+    unsafe fn vertex_attrib_pointer_f32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    );
+    unsafe fn vertex_attrib_pointer_i32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        stride: i32,
+        offset: i32,
+    );
+    unsafe fn vertex_attrib_divisor(&self, index: u32, divisor: u32);
+    unsafe fn create_texture(&self) -> Result<c_uint, String>;
+    unsafe fn delete_texture(&self, texture: c_uint);
+    unsafe fn generate_mipmap(&self, target: u32);
+    unsafe fn tex_sub_image_2d_u8_slice(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<&[u8]>,
+    );
+    unsafe fn create_framebuffer(&self) -> Result<c_uint, String>;
+    unsafe fn delete_framebuffer(&self, framebuffer: c_uint);
+    unsafe fn bind_framebuffer(&self, target: u32, framebuffer: Option<c_uint>);
+    unsafe fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<c_uint>,
+        level: i32,
+    );
+    unsafe fn check_framebuffer_status(&self, target: u32) -> u32;
+    // reads from the bound read framebuffer, errors when not available
+    unsafe fn read_pixels(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: &mut [u8],
+    ) -> Result<(), String>;
+    unsafe fn create_sampler(&self) -> Result<c_uint, String>;
+    unsafe fn delete_sampler(&self, sampler: c_uint);
+    // `unit` is the index, not TEXTURE0 + index
+    unsafe fn bind_sampler(&self, unit: u32, sampler: Option<c_uint>);
+    unsafe fn sampler_parameter_i32(
+        &self,
+        sampler: c_uint,
+        parameter: u32,
+        value: i32,
+    );
+    unsafe fn active_texture(&self, unit: u32);
+    unsafe fn bind_texture(&self, target: u32, texture: Option<c_uint>);
+    unsafe fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<&[u8]>,
+    );
+    unsafe fn tex_parameter_i32(&self, target: u32, parameter: u32, value: i32);
+    unsafe fn pixel_store_i32(&self, parameter: u32, value: i32);
+    unsafe fn uniform_1_i32(&self, location: Option<c_uint>, x: i32);
+    unsafe fn uniform_1_f32(&self, location: Option<c_uint>, x: f32);
+    unsafe fn uniform_2_f32(&self, location: Option<c_uint>, x: f32, y: f32);
+    unsafe fn uniform_3_f32(
+        &self,
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+    );
+    unsafe fn uniform_4_f32(
+        &self,
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    );
+    unsafe fn uniform_matrix_4_f32_slice(
+        &self,
+        location: Option<c_uint>,
+        transpose: bool,
+        v: &[f32; 16],
+    );
+    unsafe fn enable(&self, parameter: u32);
+    unsafe fn disable(&self, parameter: u32);
+    unsafe fn color_mask(
+        &self,
+        red: bool,
+        green: bool,
+        blue: bool,
+        alpha: bool,
+    );
+    unsafe fn line_width(&self, width: f32);
+    // desktop GL only, GLES always takes gl_PointSize
+    unsafe fn point_size(&self, size: f32);
+    unsafe fn blend_func(&self, src: u32, dst: u32);
+    unsafe fn blend_func_separate(
+        &self,
+        src_rgb: u32,
+        dst_rgb: u32,
+        src_alpha: u32,
+        dst_alpha: u32,
+    );
+    unsafe fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32);
+    unsafe fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+    unsafe fn stencil_func(&self, func: u32, reference: i32, mask: u32);
+    unsafe fn stencil_func_separate(
+        &self,
+        face: u32,
+        func: u32,
+        reference: i32,
+        mask: u32,
+    );
+    unsafe fn stencil_mask(&self, mask: u32);
+    unsafe fn stencil_mask_separate(&self, face: u32, mask: u32);
+    unsafe fn stencil_op(&self, stencil_fail: u32, depth_fail: u32, pass: u32);
+    unsafe fn stencil_op_separate(
+        &self,
+        face: u32,
+        stencil_fail: u32,
+        depth_fail: u32,
+        pass: u32,
+    );
+    unsafe fn depth_func(&self, func: u32);
+    unsafe fn depth_mask(&self, value: bool);
+    unsafe fn depth_range_f32(&self, near: f32, far: f32);
+    unsafe fn polygon_offset(&self, factor: f32, units: f32);
+    unsafe fn viewport(&self, x: i32, y: i32, width: i32, height: i32);
+    unsafe fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+    unsafe fn clear_stencil(&self, stencil: i32);
+    unsafe fn clear_depth_f32(&self, depth: f32);
+    unsafe fn clear(&self, mask: u32);
+    unsafe fn draw_elements(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+    );
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+        instance_count: i32,
+    );
+    unsafe fn draw_arrays(&self, mode: u32, first: i32, count: i32);
+    unsafe fn draw_arrays_instanced(
+        &self,
+        mode: u32,
+        first: i32,
+        count: i32,
+        instance_count: i32,
+    );
+    unsafe fn get_error(&self) -> u32;
+    unsafe fn get_parameter_i32(&self, parameter: u32) -> i32;
+    unsafe fn get_parameter_string(&self, parameter: u32) -> String;
+
+    // The glow context behind a native backend, see GL::native
+    #[cfg(not(target_arch = "wasm32"))]
+    fn native(&self) -> Option<&glow::native::Context> {
+        None
+    }
+}
+
+// glow::ActiveUniform counterpart for vertex attributes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveAttribute {
+    pub size: i32,
+    pub atype: u32,
+    pub name: String,
+}
+
+type GetProgramIv = extern "system" fn(c_uint, u32, *mut c_int);
+type GetActiveAttrib = extern "system" fn(
+    c_uint,
+    c_uint,
+    c_int,
+    *mut c_int,
+    *mut c_int,
+    *mut u32,
+    *mut c_char,
+);
+type GenerateMipmap = extern "system" fn(u32);
+type ReadPixels =
+    extern "system" fn(c_int, c_int, c_int, c_int, u32, u32, *mut c_void);
+type PointSize = extern "system" fn(f32);
+
+// glow::native::Context plus the few entry points glow 0.2 doesn't wrap
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Native {
+    context: glow::native::Context,
+    get_program_iv: Option<GetProgramIv>,
+    get_active_attrib: Option<GetActiveAttrib>,
+    generate_mipmap: Option<GenerateMipmap>,
+    read_pixels: Option<ReadPixels>,
+    point_size: Option<PointSize>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Native {
+    // The extra entry points come from SDL_GL_GetProcAddress when SDL is
+    // loaded, else from the symbols the process exports
+    pub fn new(context: glow::native::Context) -> Native {
+        Native::with_loader(context, |name| unsafe {
+            process_proc_address(name)
+        })
+    }
+
+    pub fn from_loader_function<F>(mut loader_function: F) -> Native
+    where
+        F: FnMut(&str) -> *const c_void,
+    {
+        let context =
+            glow::native::Context::from_loader_function(&mut loader_function);
+        Native::with_loader(context, loader_function)
+    }
+
+    fn with_loader<F>(
+        context: glow::native::Context,
+        mut loader_function: F,
+    ) -> Native
+    where
+        F: FnMut(&str) -> *const c_void,
+    {
+        let mut load = |name: &str| {
+            let function = loader_function(name);
+            if function.is_null() {
+                None
+            } else {
+                Some(function)
+            }
+        };
+        unsafe {
+            Native {
+                context,
+                get_program_iv: load("glGetProgramiv").map(|f| {
+                    std::mem::transmute::<*const c_void, GetProgramIv>(f)
+                }),
+                get_active_attrib: load("glGetActiveAttrib").map(|f| {
+                    std::mem::transmute::<*const c_void, GetActiveAttrib>(f)
+                }),
+                generate_mipmap: load("glGenerateMipmap").map(|f| {
+                    std::mem::transmute::<*const c_void, GenerateMipmap>(f)
+                }),
+                read_pixels: load("glReadPixels").map(|f| {
+                    std::mem::transmute::<*const c_void, ReadPixels>(f)
+                }),
+                point_size: load("glPointSize").map(|f| {
+                    std::mem::transmute::<*const c_void, PointSize>(f)
+                }),
+            }
+        }
+    }
+
+    pub fn context(&self) -> &glow::native::Context {
+        &self.context
+    }
+
+    fn program_parameter(&self, program: c_uint, parameter: u32) -> i32 {
+        let mut value = 0;
+        if let Some(get_program_iv) = self.get_program_iv {
+            get_program_iv(program, parameter, &mut value);
+        }
+        value
+    }
+}
+
+#[cfg(all(unix, not(target_arch = "wasm32")))]
+unsafe fn process_proc_address(name: &str) -> *const c_void {
+    type SdlGetProcAddress = extern "C" fn(*const c_char) -> *const c_void;
+    let name = match std::ffi::CString::new(name) {
+        Ok(name) => name,
+        Err(_) => return std::ptr::null(),
+    };
+    let sdl = libc::dlsym(
+        libc::RTLD_DEFAULT,
+        b"SDL_GL_GetProcAddress\0".as_ptr() as *const c_char,
+    );
+    if !sdl.is_null() {
+        let sdl = std::mem::transmute::<*mut c_void, SdlGetProcAddress>(sdl);
+        let function = sdl(name.as_ptr());
+        if !function.is_null() {
+            return function;
+        }
+    }
+    libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr())
+}
+
+#[cfg(all(not(unix), not(target_arch = "wasm32")))]
+unsafe fn process_proc_address(_name: &str) -> *const c_void {
+    std::ptr::null()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for Native {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Native")
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Backend for Native {
+    unsafe fn create_shader(&self, shader_type: u32) -> Result<c_uint, String> {
+        Context::create_shader(&self.context, shader_type)
+    }
+
+    unsafe fn delete_shader(&self, shader: c_uint) {
+        Context::delete_shader(&self.context, shader)
+    }
+
+    unsafe fn shader_source(&self, shader: c_uint, source: &str) {
+        Context::shader_source(&self.context, shader, source)
+    }
+
+    unsafe fn compile_shader(&self, shader: c_uint) {
+        Context::compile_shader(&self.context, shader)
+    }
+
+    unsafe fn get_shader_compile_status(&self, shader: c_uint) -> bool {
+        Context::get_shader_compile_status(&self.context, shader)
+    }
+
+    unsafe fn get_shader_info_log(&self, shader: c_uint) -> String {
+        Context::get_shader_info_log(&self.context, shader)
+    }
+
+    unsafe fn create_program(&self) -> Result<c_uint, String> {
+        Context::create_program(&self.context)
+    }
+
+    unsafe fn delete_program(&self, program: c_uint) {
+        Context::delete_program(&self.context, program)
+    }
+
+    unsafe fn attach_shader(&self, program: c_uint, shader: c_uint) {
+        Context::attach_shader(&self.context, program, shader)
+    }
+
+    unsafe fn detach_shader(&self, program: c_uint, shader: c_uint) {
+        Context::detach_shader(&self.context, program, shader)
+    }
+
+    unsafe fn bind_attrib_location(
+        &self,
+        program: c_uint,
+        index: u32,
+        name: &str,
+    ) {
+        Context::bind_attrib_location(&self.context, program, index, name)
+    }
+
+    unsafe fn link_program(&self, program: c_uint) {
+        Context::link_program(&self.context, program)
+    }
+
+    unsafe fn get_program_link_status(&self, program: c_uint) -> bool {
+        Context::get_program_link_status(&self.context, program)
+    }
+
+    unsafe fn get_program_info_log(&self, program: c_uint) -> String {
+        Context::get_program_info_log(&self.context, program)
+    }
+
+    unsafe fn get_active_uniforms(&self, program: c_uint) -> u32 {
+        Context::get_active_uniforms(&self.context, program)
+    }
+
+    // None when glGetActiveAttrib or glGetProgramiv weren't found, see
+    // Native::new
+    unsafe fn get_active_attributes(&self, program: c_uint) -> Option<u32> {
+        self.get_active_attrib.and(self.get_program_iv)?;
+        Some(self.program_parameter(program, glow::ACTIVE_ATTRIBUTES) as u32)
+    }
+
+    unsafe fn get_active_attribute(
+        &self,
+        program: c_uint,
+        index: u32,
+    ) -> Option<ActiveAttribute> {
+        let get_active_attrib = self.get_active_attrib?;
+        let max_length =
+            self.program_parameter(program, glow::ACTIVE_ATTRIBUTE_MAX_LENGTH);
+        let mut name = vec![0u8; max_length.max(1) as usize];
+        let mut length = 0;
+        let mut size = 0;
+        let mut atype = 0;
+        get_active_attrib(
+            program,
+            index,
+            name.len() as c_int,
+            &mut length,
+            &mut size,
+            &mut atype,
+            name.as_mut_ptr() as *mut c_char,
+        );
+        name.truncate(length as usize);
+        Some(ActiveAttribute {
+            size,
+            atype,
+            name: String::from_utf8_lossy(&name).into_owned(),
+        })
+    }
+
+    unsafe fn get_active_uniform(
+        &self,
+        program: c_uint,
+        index: u32,
+    ) -> Option<ActiveUniform> {
+        Context::get_active_uniform(&self.context, program, index)
+    }
+
+    unsafe fn use_program(&self, program: Option<c_uint>) {
+        Context::use_program(&self.context, program)
+    }
+
+    unsafe fn get_uniform_location(
+        &self,
+        program: c_uint,
+        name: &str,
+    ) -> Option<c_uint> {
+        Context::get_uniform_location(&self.context, program, name)
+    }
+
+    unsafe fn get_attrib_location(&self, program: c_uint, name: &str) -> i32 {
+        Context::get_attrib_location(&self.context, program, name)
+    }
+
+    unsafe fn create_buffer(&self) -> Result<c_uint, String> {
+        Context::create_buffer(&self.context)
+    }
+
+    unsafe fn delete_buffer(&self, buffer: c_uint) {
+        Context::delete_buffer(&self.context, buffer)
+    }
+
+    unsafe fn bind_buffer(&self, target: u32, buffer: Option<c_uint>) {
+        Context::bind_buffer(&self.context, target, buffer)
+    }
+
+    unsafe fn buffer_data_u8_slice(
+        &self,
+        target: u32,
+        data: &[u8],
+        usage: u32,
+    ) {
+        Context::buffer_data_u8_slice(&self.context, target, data, usage)
+    }
+
+    unsafe fn map_buffer_range(
+        &self,
+        target: u32,
+        offset: i32,
+        length: i32,
+        access: u32,
+    ) -> *mut u8 {
+        Context::map_buffer_range(&self.context, target, offset, length, access)
+    }
+
+    unsafe fn flush_mapped_buffer_range(
+        &self,
+        target: u32,
+        offset: i32,
+        length: i32,
+    ) {
+        Context::flush_mapped_buffer_range(
+            &self.context,
+            target,
+            offset,
+            length,
+        )
+    }
+
+    unsafe fn unmap_buffer(&self, target: u32) {
+        Context::unmap_buffer(&self.context, target)
+    }
+
+    unsafe fn create_vertex_array(&self) -> Result<c_uint, String> {
+        Context::create_vertex_array(&self.context)
+    }
+
+    unsafe fn delete_vertex_array(&self, vertex_array: c_uint) {
+        Context::delete_vertex_array(&self.context, vertex_array)
+    }
+
+    unsafe fn bind_vertex_array(&self, vertex_array: Option<c_uint>) {
+        Context::bind_vertex_array(&self.context, vertex_array)
+    }
+
+    unsafe fn enable_vertex_attrib_array(&self, index: u32) {
+        Context::enable_vertex_attrib_array(&self.context, index)
+    }
+
+    unsafe fn vertex_attrib_pointer_f32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    ) {
+        Context::vertex_attrib_pointer_f32(
+            &self.context,
+            index,
+            size,
+            data_type,
+            normalized,
+            stride,
+            offset,
+        )
+    }
+
+    unsafe fn vertex_attrib_pointer_i32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        stride: i32,
+        offset: i32,
+    ) {
+        Context::vertex_attrib_pointer_i32(
+            &self.context,
+            index,
+            size,
+            data_type,
+            stride,
+            offset,
+        )
+    }
+
+    unsafe fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        Context::vertex_attrib_divisor(&self.context, index, divisor)
+    }
+
+    unsafe fn create_texture(&self) -> Result<c_uint, String> {
+        Context::create_texture(&self.context)
+    }
+
+    unsafe fn delete_texture(&self, texture: c_uint) {
+        Context::delete_texture(&self.context, texture)
+    }
+
+    unsafe fn tex_sub_image_2d_u8_slice(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<&[u8]>,
+    ) {
+        Context::tex_sub_image_2d_u8_slice(
+            &self.context,
+            target,
+            level,
+            x_offset,
+            y_offset,
+            width,
+            height,
+            format,
+            ty,
+            pixels,
+        )
+    }
+
+    unsafe fn create_framebuffer(&self) -> Result<c_uint, String> {
+        Context::create_framebuffer(&self.context)
+    }
+
+    unsafe fn delete_framebuffer(&self, framebuffer: c_uint) {
+        Context::delete_framebuffer(&self.context, framebuffer)
+    }
+
+    unsafe fn bind_framebuffer(
+        &self,
+        target: u32,
+        framebuffer: Option<c_uint>,
+    ) {
+        Context::bind_framebuffer(&self.context, target, framebuffer)
+    }
+
+    unsafe fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<c_uint>,
+        level: i32,
+    ) {
+        Context::framebuffer_texture_2d(
+            &self.context,
+            target,
+            attachment,
+            texture_target,
+            texture,
+            level,
+        )
+    }
+
+    unsafe fn check_framebuffer_status(&self, target: u32) -> u32 {
+        Context::check_framebuffer_status(&self.context, target)
+    }
+
+    // the caller sizes `pixels` for width, height, format and ty
+    unsafe fn read_pixels(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: &mut [u8],
+    ) -> Result<(), String> {
+        let read_pixels = self
+            .read_pixels
+            .ok_or_else(|| "glReadPixels was not found".to_string())?;
+        read_pixels(
+            x,
+            y,
+            width,
+            height,
+            format,
+            ty,
+            pixels.as_mut_ptr() as *mut c_void,
+        );
+        Ok(())
+    }
+
+    unsafe fn create_sampler(&self) -> Result<c_uint, String> {
+        Context::create_sampler(&self.context)
+    }
+
+    unsafe fn delete_sampler(&self, sampler: c_uint) {
+        Context::delete_sampler(&self.context, sampler)
+    }
+
+    unsafe fn bind_sampler(&self, unit: u32, sampler: Option<c_uint>) {
+        Context::bind_sampler(&self.context, unit, sampler)
+    }
+
+    unsafe fn sampler_parameter_i32(
+        &self,
+        sampler: c_uint,
+        parameter: u32,
+        value: i32,
+    ) {
+        Context::sampler_parameter_i32(&self.context, sampler, parameter, value)
+    }
+
+    // without glGenerateMipmap only level 0 is used, which keeps the
+    // texture complete with mipmapped filters
+    unsafe fn generate_mipmap(&self, target: u32) {
+        match self.generate_mipmap {
+            Some(generate_mipmap) => generate_mipmap(target),
+            None => Context::tex_parameter_i32(
+                &self.context,
+                target,
+                glow::TEXTURE_MAX_LEVEL,
+                0,
+            ),
+        }
+    }
+
+    unsafe fn active_texture(&self, unit: u32) {
+        Context::active_texture(&self.context, unit)
+    }
+
+    unsafe fn bind_texture(&self, target: u32, texture: Option<c_uint>) {
+        Context::bind_texture(&self.context, target, texture)
+    }
+
+    unsafe fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<&[u8]>,
+    ) {
+        Context::tex_image_2d(
+            &self.context,
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            border,
+            format,
+            ty,
+            pixels,
+        )
+    }
+
+    unsafe fn tex_parameter_i32(
+        &self,
+        target: u32,
+        parameter: u32,
+        value: i32,
+    ) {
+        Context::tex_parameter_i32(&self.context, target, parameter, value)
+    }
+
+    unsafe fn pixel_store_i32(&self, parameter: u32, value: i32) {
+        Context::pixel_store_i32(&self.context, parameter, value)
+    }
+
+    unsafe fn uniform_1_i32(&self, location: Option<c_uint>, x: i32) {
+        Context::uniform_1_i32(&self.context, location, x)
+    }
+
+    unsafe fn uniform_1_f32(&self, location: Option<c_uint>, x: f32) {
+        Context::uniform_1_f32(&self.context, location, x)
+    }
+
+    unsafe fn uniform_2_f32(&self, location: Option<c_uint>, x: f32, y: f32) {
+        Context::uniform_2_f32(&self.context, location, x, y)
+    }
+
+    unsafe fn uniform_3_f32(
+        &self,
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+    ) {
+        Context::uniform_3_f32(&self.context, location, x, y, z)
+    }
+
+    unsafe fn uniform_4_f32(
+        &self,
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    ) {
+        Context::uniform_4_f32(&self.context, location, x, y, z, w)
+    }
+
+    unsafe fn uniform_matrix_4_f32_slice(
+        &self,
+        location: Option<c_uint>,
+        transpose: bool,
+        v: &[f32; 16],
+    ) {
+        Context::uniform_matrix_4_f32_slice(
+            &self.context,
+            location,
+            transpose,
+            v,
+        )
+    }
+
+    unsafe fn enable(&self, parameter: u32) {
+        Context::enable(&self.context, parameter)
+    }
+
+    unsafe fn disable(&self, parameter: u32) {
+        Context::disable(&self.context, parameter)
+    }
+
+    unsafe fn color_mask(
+        &self,
+        red: bool,
+        green: bool,
+        blue: bool,
+        alpha: bool,
+    ) {
+        Context::color_mask(&self.context, red, green, blue, alpha)
+    }
+
+    unsafe fn line_width(&self, width: f32) {
+        Context::line_width(&self.context, width)
+    }
+
+    unsafe fn point_size(&self, size: f32) {
+        if let Some(point_size) = self.point_size {
+            point_size(size);
+        }
+    }
+
+    unsafe fn blend_func(&self, src: u32, dst: u32) {
+        Context::blend_func(&self.context, src, dst)
+    }
+
+    unsafe fn blend_func_separate(
+        &self,
+        src_rgb: u32,
+        dst_rgb: u32,
+        src_alpha: u32,
+        dst_alpha: u32,
+    ) {
+        Context::blend_func_separate(
+            &self.context,
+            src_rgb,
+            dst_rgb,
+            src_alpha,
+            dst_alpha,
+        )
+    }
+
+    unsafe fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        Context::blend_equation_separate(&self.context, mode_rgb, mode_alpha)
+    }
+
+    unsafe fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        Context::blend_color(&self.context, red, green, blue, alpha)
+    }
+
+    unsafe fn stencil_func(&self, func: u32, reference: i32, mask: u32) {
+        Context::stencil_func(&self.context, func, reference, mask)
+    }
+
+    unsafe fn stencil_func_separate(
+        &self,
+        face: u32,
+        func: u32,
+        reference: i32,
+        mask: u32,
+    ) {
+        Context::stencil_func_separate(
+            &self.context,
+            face,
+            func,
+            reference,
+            mask,
+        )
+    }
+
+    unsafe fn stencil_mask(&self, mask: u32) {
+        Context::stencil_mask(&self.context, mask)
+    }
+
+    unsafe fn stencil_mask_separate(&self, face: u32, mask: u32) {
+        Context::stencil_mask_separate(&self.context, face, mask)
+    }
+
+    unsafe fn stencil_op(&self, stencil_fail: u32, depth_fail: u32, pass: u32) {
+        Context::stencil_op(&self.context, stencil_fail, depth_fail, pass)
+    }
+
+    unsafe fn stencil_op_separate(
+        &self,
+        face: u32,
+        stencil_fail: u32,
+        depth_fail: u32,
+        pass: u32,
+    ) {
+        Context::stencil_op_separate(
+            &self.context,
+            face,
+            stencil_fail,
+            depth_fail,
+            pass,
+        )
+    }
+
+    unsafe fn depth_func(&self, func: u32) {
+        Context::depth_func(&self.context, func)
+    }
+
+    unsafe fn depth_mask(&self, value: bool) {
+        Context::depth_mask(&self.context, value)
+    }
+
+    unsafe fn depth_range_f32(&self, near: f32, far: f32) {
+        Context::depth_range_f32(&self.context, near, far)
+    }
+
+    unsafe fn polygon_offset(&self, factor: f32, units: f32) {
+        Context::polygon_offset(&self.context, factor, units)
+    }
+
+    unsafe fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        Context::viewport(&self.context, x, y, width, height)
+    }
+
+    unsafe fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        Context::clear_color(&self.context, red, green, blue, alpha)
+    }
+
+    unsafe fn clear_stencil(&self, stencil: i32) {
+        Context::clear_stencil(&self.context, stencil)
+    }
+
+    unsafe fn clear_depth_f32(&self, depth: f32) {
+        Context::clear_depth_f32(&self.context, depth)
+    }
+
+    unsafe fn clear(&self, mask: u32) {
+        Context::clear(&self.context, mask)
+    }
+
+    unsafe fn draw_elements(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+    ) {
+        Context::draw_elements(&self.context, mode, count, element_type, offset)
+    }
+
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+        instance_count: i32,
+    ) {
+        Context::draw_elements_instanced(
+            &self.context,
+            mode,
+            count,
+            element_type,
+            offset,
+            instance_count,
+        )
+    }
+
+    unsafe fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        Context::draw_arrays(&self.context, mode, first, count)
+    }
+
+    unsafe fn draw_arrays_instanced(
+        &self,
+        mode: u32,
+        first: i32,
+        count: i32,
+        instance_count: i32,
+    ) {
+        Context::draw_arrays_instanced(
+            &self.context,
+            mode,
+            first,
+            count,
+            instance_count,
+        )
+    }
+
+    unsafe fn get_error(&self) -> u32 {
+        Context::get_error(&self.context)
+    }
+
+    unsafe fn get_parameter_i32(&self, parameter: u32) -> i32 {
+        Context::get_parameter_i32(&self.context, parameter)
+    }
+
+    unsafe fn get_parameter_string(&self, parameter: u32) -> String {
+        Context::get_parameter_string(&self.context, parameter)
+    }
+
+    fn native(&self) -> Option<&glow::native::Context> {
+        Some(&self.context)
+    }
+}
@@ -1,8 +1,9 @@
 use super::shader;
-use super::GL;
+use super::{Error, GL};
 use glow;
-use glow::Context;
+use std::cell::{Cell, RefCell};
 use std::os::raw::c_uint;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct MapArray<'a, T>
@@ -16,7 +17,7 @@ where
 }
 
 impl<'a, T> MapArray<'a, T> {
-    pub unsafe fn new(gl: &GL, len: usize) -> Result<MapArray<T>, String> {
+    pub unsafe fn new(gl: &GL, len: usize) -> Result<MapArray<T>, Error> {
         let ptr = gl.map_buffer_range(
             glow::ARRAY_BUFFER,
             0,
@@ -24,10 +25,7 @@ impl<'a, T> MapArray<'a, T> {
             glow::MAP_WRITE_BIT | glow::MAP_FLUSH_EXPLICIT_BIT,
         ) as *mut T;
         if ptr.is_null() {
-            return Err(format!(
-                "buffer map is null pointer. Error_number={}",
-                gl.get_error()
-            ));
+            return Err(Error::BufferMap(gl.get_error()));
         }
         let res = ::std::slice::from_raw_parts_mut(ptr, len);
         Ok(MapArray {
@@ -57,8 +55,13 @@ pub struct IndexBuffer {
 }
 
 impl IndexBuffer {
-    pub fn new(gl: &GL, index: &[u16]) -> Result<IndexBuffer, String> {
+    pub fn new(gl: &GL, index: &[u16]) -> Result<IndexBuffer, Error> {
         let veb = ElementArrayBuffer::new(&gl)?;
+        // the element binding is part of the bound vertex array, which
+        // Frame::draw leaves bound
+        unsafe {
+            gl.bind_vertex_array(None);
+        }
         veb.bind();
         veb.element_draw_data(index); // is it safe without thoose binds
         veb.unbind();
@@ -82,9 +85,22 @@ pub trait VertexBufferBehavior {
 
     fn unbind(&self);
 
-    fn vertex_attrib_pointers(&self, gl: &GL, program: &shader::Program);
+    // Err(Error::AttributeLocation) when the program disagrees with a
+    // fixed location of the vertex type
+    fn vertex_attrib_pointers(
+        &self,
+        gl: &GL,
+        program: &shader::Program,
+    ) -> Result<(), Error>;
+
+    // number of elements uploaded, None before any data. Vertex arrays
+    // laid out from the buffer share it, see Program::set_layout
+    fn vertex_count(&self) -> VertexCount;
 }
 
+// Element count of a vertex buffer, updated when new data is uploaded
+pub type VertexCount = Rc<Cell<Option<usize>>>;
+
 pub trait BufferType {
     const BUFFER_TYPE: c_uint;
 }
@@ -112,8 +128,8 @@ impl<B> Buffer<B>
 where
     B: BufferType,
 {
-    pub fn new(gl: &GL) -> Result<Buffer<B>, String> {
-        let vbo = unsafe { gl.create_buffer()? };
+    pub fn new(gl: &GL) -> Result<Buffer<B>, Error> {
+        let vbo = unsafe { gl.create_buffer().map_err(Error::ObjectCreation)? };
         Ok(Buffer {
             gl: gl.clone(),
             vbo,
@@ -188,17 +204,37 @@ pub type ElementArrayBuffer = Buffer<BufferTypeElementArray>;
 pub struct VertexArray {
     gl: GL,
     vao: c_uint,
+    // number of vertices in the buffer owning this vao or the first buffer
+    // given to Program::set_layout, used by non-indexed draws
+    vertex_count: RefCell<VertexCount>,
 }
 
 impl VertexArray {
-    pub fn new(gl: &GL) -> Result<VertexArray, String> {
-        let vao = unsafe { gl.create_vertex_array()? };
+    pub fn new(gl: &GL) -> Result<VertexArray, Error> {
+        let vao =
+            unsafe { gl.create_vertex_array().map_err(Error::ObjectCreation)? };
         Ok(VertexArray {
             gl: gl.clone(),
             vao,
+            vertex_count: RefCell::new(Rc::new(Cell::new(None))),
         })
     }
 
+    // A fixed count, no longer following a buffer
+    pub fn set_vertex_count(&self, vertex_count: usize) {
+        *self.vertex_count.borrow_mut() =
+            Rc::new(Cell::new(Some(vertex_count)));
+    }
+
+    // Follows the count of a buffer as it grows or shrinks
+    pub fn share_vertex_count(&self, vertex_count: VertexCount) {
+        *self.vertex_count.borrow_mut() = vertex_count;
+    }
+
+    pub fn vertex_count(&self) -> Option<usize> {
+        self.vertex_count.borrow().get()
+    }
+
     pub fn bind(&self) {
         unsafe {
             self.gl.bind_vertex_array(Some(self.vao));
@@ -0,0 +1,874 @@
+// Backend wrapper remembering the state last sent to the driver and dropping
+// calls that would not change it. Every GL goes through one.
+use super::backend::{ActiveAttribute, Backend};
+use glow::ActiveUniform;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::os::raw::c_uint;
+
+// issued/skipped only count calls the cache knows about (binds and fixed
+// function state), the rest is always forwarded
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub issued: usize,
+    pub skipped: usize,
+}
+
+// None means unknown, so the next call is always issued
+#[derive(Debug, Default)]
+struct State {
+    program: Option<Option<c_uint>>,
+    vertex_array: Option<Option<c_uint>>,
+    buffers: HashMap<u32, Option<c_uint>>,
+    active_texture: Option<u32>,
+    // keyed by (unit, target)
+    textures: HashMap<(u32, u32), Option<c_uint>>,
+    // keyed by unit
+    samplers: HashMap<u32, Option<c_uint>>,
+    framebuffers: HashMap<u32, Option<c_uint>>,
+    capabilities: HashMap<u32, bool>,
+    color_mask: Option<(bool, bool, bool, bool)>,
+    blend_func: Option<(u32, u32, u32, u32)>,
+    blend_equation: Option<(u32, u32)>,
+    blend_color: Option<(f32, f32, f32, f32)>,
+    // front and back faces
+    stencil_func: [Option<(u32, i32, u32)>; 2],
+    stencil_op: [Option<(u32, u32, u32)>; 2],
+    stencil_mask: [Option<u32>; 2],
+    depth_func: Option<u32>,
+    depth_mask: Option<bool>,
+    depth_range: Option<(f32, f32)>,
+    polygon_offset: Option<(f32, f32)>,
+    line_width: Option<f32>,
+    point_size: Option<f32>,
+    viewport: Option<(i32, i32, i32, i32)>,
+    clear_color: Option<(f32, f32, f32, f32)>,
+    clear_stencil: Option<i32>,
+    clear_depth: Option<f32>,
+}
+
+fn faces(face: u32) -> &'static [usize] {
+    match face {
+        glow::FRONT => &[0],
+        glow::BACK => &[1],
+        _ => &[0, 1],
+    }
+}
+
+#[derive(Debug)]
+pub struct StateCache {
+    backend: Box<dyn Backend>,
+    state: RefCell<State>,
+    enabled: Cell<bool>,
+    stats: Cell<CacheStats>,
+    // glGetString results never change for a context
+    strings: RefCell<HashMap<u32, String>>,
+}
+
+impl StateCache {
+    pub fn new(backend: Box<dyn Backend>) -> StateCache {
+        StateCache {
+            backend,
+            state: RefCell::new(State::default()),
+            enabled: Cell::new(true),
+            stats: Cell::new(CacheStats::default()),
+            strings: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats.get()
+    }
+
+    pub fn reset_stats(&self) {
+        self.stats.set(CacheStats::default());
+    }
+
+    // call after touching the context behind red's back, e.g. through
+    // GL::native
+    pub fn invalidate(&self) {
+        *self.state.borrow_mut() = State::default();
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.invalidate();
+        self.enabled.set(enabled);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    fn count(&self, issued: bool) -> bool {
+        let mut stats = self.stats.get();
+        if issued {
+            stats.issued += 1;
+        } else {
+            stats.skipped += 1;
+        }
+        self.stats.set(stats);
+        issued
+    }
+
+    // stores `value` and tells whether the call has to reach the driver
+    fn update<T: PartialEq>(
+        &self,
+        slot: impl FnOnce(&mut State) -> &mut Option<T>,
+        value: T,
+    ) -> bool {
+        if !self.enabled.get() {
+            return self.count(true);
+        }
+        let mut state = self.state.borrow_mut();
+        let slot = slot(&mut state);
+        if slot.as_ref() == Some(&value) {
+            return self.count(false);
+        }
+        *slot = Some(value);
+        self.count(true)
+    }
+
+    fn update_faces<T: PartialEq + Copy>(
+        &self,
+        slots: impl FnOnce(&mut State) -> &mut [Option<T>; 2],
+        face: u32,
+        value: T,
+    ) -> bool {
+        if !self.enabled.get() {
+            return self.count(true);
+        }
+        let mut state = self.state.borrow_mut();
+        let slots = slots(&mut state);
+        let faces = faces(face);
+        if faces.iter().all(|&i| slots[i] == Some(value)) {
+            return self.count(false);
+        }
+        for &i in faces {
+            slots[i] = Some(value);
+        }
+        self.count(true)
+    }
+
+    fn update_map<K: std::hash::Hash + Eq, T: PartialEq>(
+        &self,
+        map: impl FnOnce(&mut State) -> &mut HashMap<K, T>,
+        key: K,
+        value: T,
+    ) -> bool {
+        if !self.enabled.get() {
+            return self.count(true);
+        }
+        let mut state = self.state.borrow_mut();
+        let map = map(&mut state);
+        if map.get(&key) == Some(&value) {
+            return self.count(false);
+        }
+        map.insert(key, value);
+        self.count(true)
+    }
+}
+
+impl Backend for StateCache {
+    unsafe fn create_shader(&self, shader_type: u32) -> Result<c_uint, String> {
+        self.backend.create_shader(shader_type)
+    }
+
+    unsafe fn delete_shader(&self, shader: c_uint) {
+        self.backend.delete_shader(shader)
+    }
+
+    unsafe fn shader_source(&self, shader: c_uint, source: &str) {
+        self.backend.shader_source(shader, source)
+    }
+
+    unsafe fn compile_shader(&self, shader: c_uint) {
+        self.backend.compile_shader(shader)
+    }
+
+    unsafe fn get_shader_compile_status(&self, shader: c_uint) -> bool {
+        self.backend.get_shader_compile_status(shader)
+    }
+
+    unsafe fn get_shader_info_log(&self, shader: c_uint) -> String {
+        self.backend.get_shader_info_log(shader)
+    }
+
+    unsafe fn create_program(&self) -> Result<c_uint, String> {
+        self.backend.create_program()
+    }
+
+    unsafe fn delete_program(&self, program: c_uint) {
+        if self.state.borrow().program == Some(Some(program)) {
+            self.state.borrow_mut().program = None;
+        }
+        self.backend.delete_program(program)
+    }
+
+    unsafe fn attach_shader(&self, program: c_uint, shader: c_uint) {
+        self.backend.attach_shader(program, shader)
+    }
+
+    unsafe fn detach_shader(&self, program: c_uint, shader: c_uint) {
+        self.backend.detach_shader(program, shader)
+    }
+
+    unsafe fn bind_attrib_location(
+        &self,
+        program: c_uint,
+        index: u32,
+        name: &str,
+    ) {
+        self.backend.bind_attrib_location(program, index, name)
+    }
+
+    unsafe fn link_program(&self, program: c_uint) {
+        self.backend.link_program(program)
+    }
+
+    unsafe fn get_program_link_status(&self, program: c_uint) -> bool {
+        self.backend.get_program_link_status(program)
+    }
+
+    unsafe fn get_program_info_log(&self, program: c_uint) -> String {
+        self.backend.get_program_info_log(program)
+    }
+
+    unsafe fn get_active_uniforms(&self, program: c_uint) -> u32 {
+        self.backend.get_active_uniforms(program)
+    }
+
+    unsafe fn get_active_attributes(&self, program: c_uint) -> Option<u32> {
+        self.backend.get_active_attributes(program)
+    }
+
+    unsafe fn get_active_attribute(
+        &self,
+        program: c_uint,
+        index: u32,
+    ) -> Option<ActiveAttribute> {
+        self.backend.get_active_attribute(program, index)
+    }
+
+    unsafe fn get_active_uniform(
+        &self,
+        program: c_uint,
+        index: u32,
+    ) -> Option<ActiveUniform> {
+        self.backend.get_active_uniform(program, index)
+    }
+
+    unsafe fn use_program(&self, program: Option<c_uint>) {
+        if self.update(|s| &mut s.program, program) {
+            self.backend.use_program(program)
+        }
+    }
+
+    unsafe fn get_uniform_location(
+        &self,
+        program: c_uint,
+        name: &str,
+    ) -> Option<c_uint> {
+        self.backend.get_uniform_location(program, name)
+    }
+
+    unsafe fn get_attrib_location(&self, program: c_uint, name: &str) -> i32 {
+        self.backend.get_attrib_location(program, name)
+    }
+
+    unsafe fn create_buffer(&self) -> Result<c_uint, String> {
+        self.backend.create_buffer()
+    }
+
+    unsafe fn delete_buffer(&self, buffer: c_uint) {
+        // deleted buffers are unbound from every target they were bound to
+        self.state
+            .borrow_mut()
+            .buffers
+            .retain(|_, bound| *bound != Some(buffer));
+        self.backend.delete_buffer(buffer)
+    }
+
+    unsafe fn bind_buffer(&self, target: u32, buffer: Option<c_uint>) {
+        if self.update_map(|s| &mut s.buffers, target, buffer) {
+            self.backend.bind_buffer(target, buffer)
+        }
+    }
+
+    unsafe fn buffer_data_u8_slice(
+        &self,
+        target: u32,
+        data: &[u8],
+        usage: u32,
+    ) {
+        self.backend.buffer_data_u8_slice(target, data, usage)
+    }
+
+    unsafe fn map_buffer_range(
+        &self,
+        target: u32,
+        offset: i32,
+        length: i32,
+        access: u32,
+    ) -> *mut u8 {
+        self.backend
+            .map_buffer_range(target, offset, length, access)
+    }
+
+    unsafe fn flush_mapped_buffer_range(
+        &self,
+        target: u32,
+        offset: i32,
+        length: i32,
+    ) {
+        self.backend
+            .flush_mapped_buffer_range(target, offset, length)
+    }
+
+    unsafe fn unmap_buffer(&self, target: u32) {
+        self.backend.unmap_buffer(target)
+    }
+
+    unsafe fn create_vertex_array(&self) -> Result<c_uint, String> {
+        self.backend.create_vertex_array()
+    }
+
+    unsafe fn delete_vertex_array(&self, vertex_array: c_uint) {
+        if self.state.borrow().vertex_array == Some(Some(vertex_array)) {
+            let mut state = self.state.borrow_mut();
+            state.vertex_array = None;
+            state.buffers.remove(&glow::ELEMENT_ARRAY_BUFFER);
+        }
+        self.backend.delete_vertex_array(vertex_array)
+    }
+
+    unsafe fn bind_vertex_array(&self, vertex_array: Option<c_uint>) {
+        if self.update(|s| &mut s.vertex_array, vertex_array) {
+            // the element array binding is part of the vertex array state
+            self.state
+                .borrow_mut()
+                .buffers
+                .remove(&glow::ELEMENT_ARRAY_BUFFER);
+            self.backend.bind_vertex_array(vertex_array)
+        }
+    }
+
+    unsafe fn enable_vertex_attrib_array(&self, index: u32) {
+        self.backend.enable_vertex_attrib_array(index)
+    }
+
+    unsafe fn vertex_attrib_pointer_f32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    ) {
+        self.backend.vertex_attrib_pointer_f32(
+            index, size, data_type, normalized, stride, offset,
+        )
+    }
+
+    unsafe fn vertex_attrib_pointer_i32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        stride: i32,
+        offset: i32,
+    ) {
+        self.backend
+            .vertex_attrib_pointer_i32(index, size, data_type, stride, offset)
+    }
+
+    unsafe fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.backend.vertex_attrib_divisor(index, divisor)
+    }
+
+    unsafe fn create_texture(&self) -> Result<c_uint, String> {
+        self.backend.create_texture()
+    }
+
+    unsafe fn delete_texture(&self, texture: c_uint) {
+        // deleted textures are unbound from every unit they were bound to
+        self.state
+            .borrow_mut()
+            .textures
+            .retain(|_, bound| *bound != Some(texture));
+        self.backend.delete_texture(texture)
+    }
+
+    unsafe fn generate_mipmap(&self, target: u32) {
+        self.backend.generate_mipmap(target)
+    }
+
+    unsafe fn tex_sub_image_2d_u8_slice(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<&[u8]>,
+    ) {
+        self.backend.tex_sub_image_2d_u8_slice(
+            target, level, x_offset, y_offset, width, height, format, ty,
+            pixels,
+        )
+    }
+
+    unsafe fn create_framebuffer(&self) -> Result<c_uint, String> {
+        self.backend.create_framebuffer()
+    }
+
+    unsafe fn delete_framebuffer(&self, framebuffer: c_uint) {
+        self.state
+            .borrow_mut()
+            .framebuffers
+            .retain(|_, bound| *bound != Some(framebuffer));
+        self.backend.delete_framebuffer(framebuffer)
+    }
+
+    unsafe fn bind_framebuffer(
+        &self,
+        target: u32,
+        framebuffer: Option<c_uint>,
+    ) {
+        if target == glow::FRAMEBUFFER {
+            // FRAMEBUFFER sets both the draw and the read binding
+            let mut state = self.state.borrow_mut();
+            state.framebuffers.remove(&glow::DRAW_FRAMEBUFFER);
+            state.framebuffers.remove(&glow::READ_FRAMEBUFFER);
+        } else {
+            self.state
+                .borrow_mut()
+                .framebuffers
+                .remove(&glow::FRAMEBUFFER);
+        }
+        if self.update_map(|s| &mut s.framebuffers, target, framebuffer) {
+            self.backend.bind_framebuffer(target, framebuffer)
+        }
+    }
+
+    unsafe fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<c_uint>,
+        level: i32,
+    ) {
+        self.backend.framebuffer_texture_2d(
+            target,
+            attachment,
+            texture_target,
+            texture,
+            level,
+        )
+    }
+
+    unsafe fn check_framebuffer_status(&self, target: u32) -> u32 {
+        self.backend.check_framebuffer_status(target)
+    }
+
+    unsafe fn read_pixels(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: &mut [u8],
+    ) -> Result<(), String> {
+        self.backend
+            .read_pixels(x, y, width, height, format, ty, pixels)
+    }
+
+    unsafe fn create_sampler(&self) -> Result<c_uint, String> {
+        self.backend.create_sampler()
+    }
+
+    unsafe fn delete_sampler(&self, sampler: c_uint) {
+        self.state
+            .borrow_mut()
+            .samplers
+            .retain(|_, bound| *bound != Some(sampler));
+        self.backend.delete_sampler(sampler)
+    }
+
+    unsafe fn bind_sampler(&self, unit: u32, sampler: Option<c_uint>) {
+        if self.update_map(|s| &mut s.samplers, unit, sampler) {
+            self.backend.bind_sampler(unit, sampler)
+        }
+    }
+
+    unsafe fn sampler_parameter_i32(
+        &self,
+        sampler: c_uint,
+        parameter: u32,
+        value: i32,
+    ) {
+        self.backend
+            .sampler_parameter_i32(sampler, parameter, value)
+    }
+
+    unsafe fn active_texture(&self, unit: u32) {
+        if self.update(|s| &mut s.active_texture, unit) {
+            self.backend.active_texture(unit)
+        }
+    }
+
+    unsafe fn bind_texture(&self, target: u32, texture: Option<c_uint>) {
+        let unit = self.state.borrow().active_texture;
+        let issue = match unit {
+            Some(unit) => {
+                self.update_map(|s| &mut s.textures, (unit, target), texture)
+            }
+            None => self.count(true),
+        };
+        if issue {
+            self.backend.bind_texture(target, texture)
+        }
+    }
+
+    unsafe fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<&[u8]>,
+    ) {
+        self.backend.tex_image_2d(
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            border,
+            format,
+            ty,
+            pixels,
+        )
+    }
+
+    unsafe fn tex_parameter_i32(
+        &self,
+        target: u32,
+        parameter: u32,
+        value: i32,
+    ) {
+        self.backend.tex_parameter_i32(target, parameter, value)
+    }
+
+    unsafe fn pixel_store_i32(&self, parameter: u32, value: i32) {
+        self.backend.pixel_store_i32(parameter, value)
+    }
+
+    unsafe fn uniform_1_i32(&self, location: Option<c_uint>, x: i32) {
+        self.backend.uniform_1_i32(location, x)
+    }
+
+    unsafe fn uniform_1_f32(&self, location: Option<c_uint>, x: f32) {
+        self.backend.uniform_1_f32(location, x)
+    }
+
+    unsafe fn uniform_2_f32(&self, location: Option<c_uint>, x: f32, y: f32) {
+        self.backend.uniform_2_f32(location, x, y)
+    }
+
+    unsafe fn uniform_3_f32(
+        &self,
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+    ) {
+        self.backend.uniform_3_f32(location, x, y, z)
+    }
+
+    unsafe fn uniform_4_f32(
+        &self,
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    ) {
+        self.backend.uniform_4_f32(location, x, y, z, w)
+    }
+
+    unsafe fn uniform_matrix_4_f32_slice(
+        &self,
+        location: Option<c_uint>,
+        transpose: bool,
+        v: &[f32; 16],
+    ) {
+        self.backend
+            .uniform_matrix_4_f32_slice(location, transpose, v)
+    }
+
+    unsafe fn enable(&self, parameter: u32) {
+        if self.update_map(|s| &mut s.capabilities, parameter, true) {
+            self.backend.enable(parameter)
+        }
+    }
+
+    unsafe fn disable(&self, parameter: u32) {
+        if self.update_map(|s| &mut s.capabilities, parameter, false) {
+            self.backend.disable(parameter)
+        }
+    }
+
+    unsafe fn color_mask(
+        &self,
+        red: bool,
+        green: bool,
+        blue: bool,
+        alpha: bool,
+    ) {
+        let mask = (red, green, blue, alpha);
+        if self.update(|s| &mut s.color_mask, mask) {
+            self.backend.color_mask(red, green, blue, alpha)
+        }
+    }
+
+    unsafe fn line_width(&self, width: f32) {
+        if self.update(|s| &mut s.line_width, width) {
+            self.backend.line_width(width)
+        }
+    }
+
+    unsafe fn point_size(&self, size: f32) {
+        if self.update(|s| &mut s.point_size, size) {
+            self.backend.point_size(size)
+        }
+    }
+
+    unsafe fn blend_func(&self, src: u32, dst: u32) {
+        if self.update(|s| &mut s.blend_func, (src, dst, src, dst)) {
+            self.backend.blend_func(src, dst)
+        }
+    }
+
+    unsafe fn blend_func_separate(
+        &self,
+        src_rgb: u32,
+        dst_rgb: u32,
+        src_alpha: u32,
+        dst_alpha: u32,
+    ) {
+        let func = (src_rgb, dst_rgb, src_alpha, dst_alpha);
+        if self.update(|s| &mut s.blend_func, func) {
+            self.backend
+                .blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha)
+        }
+    }
+
+    unsafe fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        let equation = (mode_rgb, mode_alpha);
+        if self.update(|s| &mut s.blend_equation, equation) {
+            self.backend.blend_equation_separate(mode_rgb, mode_alpha)
+        }
+    }
+
+    unsafe fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        let color = (red, green, blue, alpha);
+        if self.update(|s| &mut s.blend_color, color) {
+            self.backend.blend_color(red, green, blue, alpha)
+        }
+    }
+
+    unsafe fn stencil_func(&self, func: u32, reference: i32, mask: u32) {
+        let func = (func, reference, mask);
+        if self.update_faces(
+            |s| &mut s.stencil_func,
+            glow::FRONT_AND_BACK,
+            func,
+        ) {
+            self.backend.stencil_func(func.0, reference, mask)
+        }
+    }
+
+    unsafe fn stencil_func_separate(
+        &self,
+        face: u32,
+        func: u32,
+        reference: i32,
+        mask: u32,
+    ) {
+        let func = (func, reference, mask);
+        if self.update_faces(|s| &mut s.stencil_func, face, func) {
+            self.backend
+                .stencil_func_separate(face, func.0, reference, mask)
+        }
+    }
+
+    unsafe fn stencil_mask(&self, mask: u32) {
+        if self.update_faces(
+            |s| &mut s.stencil_mask,
+            glow::FRONT_AND_BACK,
+            mask,
+        ) {
+            self.backend.stencil_mask(mask)
+        }
+    }
+
+    unsafe fn stencil_mask_separate(&self, face: u32, mask: u32) {
+        if self.update_faces(|s| &mut s.stencil_mask, face, mask) {
+            self.backend.stencil_mask_separate(face, mask)
+        }
+    }
+
+    unsafe fn stencil_op(&self, stencil_fail: u32, depth_fail: u32, pass: u32) {
+        let op = (stencil_fail, depth_fail, pass);
+        if self.update_faces(|s| &mut s.stencil_op, glow::FRONT_AND_BACK, op) {
+            self.backend.stencil_op(stencil_fail, depth_fail, pass)
+        }
+    }
+
+    unsafe fn stencil_op_separate(
+        &self,
+        face: u32,
+        stencil_fail: u32,
+        depth_fail: u32,
+        pass: u32,
+    ) {
+        let op = (stencil_fail, depth_fail, pass);
+        if self.update_faces(|s| &mut s.stencil_op, face, op) {
+            self.backend.stencil_op_separate(
+                face,
+                stencil_fail,
+                depth_fail,
+                pass,
+            )
+        }
+    }
+
+    unsafe fn depth_func(&self, func: u32) {
+        if self.update(|s| &mut s.depth_func, func) {
+            self.backend.depth_func(func)
+        }
+    }
+
+    unsafe fn depth_mask(&self, value: bool) {
+        if self.update(|s| &mut s.depth_mask, value) {
+            self.backend.depth_mask(value)
+        }
+    }
+
+    unsafe fn depth_range_f32(&self, near: f32, far: f32) {
+        if self.update(|s| &mut s.depth_range, (near, far)) {
+            self.backend.depth_range_f32(near, far)
+        }
+    }
+
+    unsafe fn polygon_offset(&self, factor: f32, units: f32) {
+        if self.update(|s| &mut s.polygon_offset, (factor, units)) {
+            self.backend.polygon_offset(factor, units)
+        }
+    }
+
+    unsafe fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        if self.update(|s| &mut s.viewport, (x, y, width, height)) {
+            self.backend.viewport(x, y, width, height)
+        }
+    }
+
+    unsafe fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        let color = (red, green, blue, alpha);
+        if self.update(|s| &mut s.clear_color, color) {
+            self.backend.clear_color(red, green, blue, alpha)
+        }
+    }
+
+    unsafe fn clear_stencil(&self, stencil: i32) {
+        if self.update(|s| &mut s.clear_stencil, stencil) {
+            self.backend.clear_stencil(stencil)
+        }
+    }
+
+    unsafe fn clear_depth_f32(&self, depth: f32) {
+        if self.update(|s| &mut s.clear_depth, depth) {
+            self.backend.clear_depth_f32(depth)
+        }
+    }
+
+    unsafe fn clear(&self, mask: u32) {
+        self.backend.clear(mask)
+    }
+
+    unsafe fn draw_elements(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+    ) {
+        self.backend
+            .draw_elements(mode, count, element_type, offset)
+    }
+
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+        instance_count: i32,
+    ) {
+        self.backend.draw_elements_instanced(
+            mode,
+            count,
+            element_type,
+            offset,
+            instance_count,
+        )
+    }
+
+    unsafe fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        self.backend.draw_arrays(mode, first, count)
+    }
+
+    unsafe fn draw_arrays_instanced(
+        &self,
+        mode: u32,
+        first: i32,
+        count: i32,
+        instance_count: i32,
+    ) {
+        self.backend
+            .draw_arrays_instanced(mode, first, count, instance_count)
+    }
+
+    unsafe fn get_error(&self) -> u32 {
+        self.backend.get_error()
+    }
+
+    unsafe fn get_parameter_i32(&self, parameter: u32) -> i32 {
+        self.backend.get_parameter_i32(parameter)
+    }
+
+    unsafe fn get_parameter_string(&self, parameter: u32) -> String {
+        if let Some(string) = self.strings.borrow().get(&parameter) {
+            return string.clone();
+        }
+        let string = self.backend.get_parameter_string(parameter);
+        self.strings.borrow_mut().insert(parameter, string.clone());
+        string
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn native(&self) -> Option<&glow::native::Context> {
+        self.backend.native()
+    }
+}
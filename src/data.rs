@@ -1,6 +1,6 @@
+use super::reflect::BaseType;
 use super::GL;
 use crate::shader::Program;
-use glow::Context;
 use std::os::raw::{c_int, c_uint};
 
 #[derive(Copy, Clone, Debug)]
@@ -10,6 +10,9 @@ pub struct f32_ {
 }
 
 impl f32_ {
+    pub const COMPONENTS: usize = 1;
+    pub const BASE_TYPE: BaseType = BaseType::Float;
+
     pub fn new(d0: f32) -> f32_ {
         f32_ { d0 }
     }
@@ -47,6 +50,9 @@ pub struct i32_ {
 }
 
 impl i32_ {
+    pub const COMPONENTS: usize = 1;
+    pub const BASE_TYPE: BaseType = BaseType::Int;
+
     pub fn new(d0: i32) -> i32_ {
         i32_ { d0 }
     }
@@ -80,6 +86,9 @@ impl From<i32> for i32_ {
 pub struct f32_f32_f32(pub f32, pub f32, pub f32);
 
 impl f32_f32_f32 {
+    pub const COMPONENTS: usize = 3;
+    pub const BASE_TYPE: BaseType = BaseType::Float;
+
     pub fn new(d0: f32, d1: f32, d2: f32) -> f32_f32_f32 {
         f32_f32_f32(d0, d1, d2)
     }
@@ -114,6 +123,9 @@ impl From<(f32, f32, f32)> for f32_f32_f32 {
 pub struct f32_f32_f32_f32(pub f32, pub f32, pub f32, pub f32);
 
 impl f32_f32_f32_f32 {
+    pub const COMPONENTS: usize = 4;
+    pub const BASE_TYPE: BaseType = BaseType::Float;
+
     pub fn new(d0: f32, d1: f32, d2: f32, d3: f32) -> f32_f32_f32_f32 {
         f32_f32_f32_f32(d0, d1, d2, d3)
     }
@@ -151,6 +163,9 @@ pub struct f32_f32 {
 }
 
 impl f32_f32 {
+    pub const COMPONENTS: usize = 2;
+    pub const BASE_TYPE: BaseType = BaseType::Float;
+
     pub fn new(d0: f32, d1: f32) -> f32_f32 {
         f32_f32 { d0, d1 }
     }
@@ -179,6 +194,23 @@ impl From<(f32, f32)> for f32_f32 {
     }
 }
 
+// One field of a vertex type, as fed to vertex_attrib_pointer
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexAttribute {
+    pub name: &'static str,
+    pub components: usize,
+    pub base_type: BaseType,
+    // #[location = "N"], bound before linking by
+    // Program::from_shaders_with_layout
+    pub location: Option<u32>,
+}
+
+// Implemented by #[derive(VertexAttribPointers)], see
+// Program::validate_vertex
+pub trait VertexLayout {
+    fn attributes() -> Vec<VertexAttribute>;
+}
+
 pub trait Vertex {
     fn vertex_attrib_pointers(gl: &GL, program: &Program);
 }
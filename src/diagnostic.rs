@@ -0,0 +1,282 @@
+// Parsing of driver shader info logs. Understood formats:
+//   Mesa:          0:12(5): error: message
+//   NVIDIA:        0(12) : error C0000: message
+//   AMD and others ERROR: 0:12: message
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    // log lines in a format we don't know
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // source string number, the first number in "0:12"
+    pub source: Option<u32>,
+    // name of the file that source string came from, see preprocessor
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+    // the offending source line, if it could be found
+    pub code: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.severity)?;
+        if let Some(line) = self.line {
+            match &self.file {
+                Some(file) => write!(f, " at {}:{}", file, line)?,
+                None => write!(f, " at {}:{}", self.source.unwrap_or(0), line)?,
+            }
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        write!(f, ": {}", self.message)?;
+        if let (Some(line), Some(code)) = (self.line, &self.code) {
+            let number = line.to_string();
+            let gutter = " ".repeat(number.len());
+            write!(f, "\n {} |\n {} | {}", gutter, number, code)?;
+            if let Some(column) = self.column {
+                let offset = " ".repeat(column.saturating_sub(1) as usize);
+                write!(f, "\n {} | {}^", gutter, offset)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn split_number(s: &str) -> Option<(u32, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if end == 0 {
+        return None;
+    }
+    Some((s[..end].parse().ok()?, &s[end..]))
+}
+
+fn parse_severity(s: &str) -> Option<(Severity, &str)> {
+    let lower = s.to_ascii_lowercase();
+    if lower.starts_with("error") {
+        Some((Severity::Error, &s["error".len()..]))
+    } else if lower.starts_with("warning") {
+        Some((Severity::Warning, &s["warning".len()..]))
+    } else {
+        None
+    }
+}
+
+fn diagnostic(
+    severity: Severity,
+    source: u32,
+    line: u32,
+    column: Option<u32>,
+    message: &str,
+) -> Diagnostic {
+    Diagnostic {
+        severity,
+        source: Some(source),
+        file: None,
+        line: Some(line),
+        column,
+        message: message.trim().to_string(),
+        code: None,
+    }
+}
+
+// 0:12(5): error: message
+fn parse_mesa(text: &str) -> Option<Diagnostic> {
+    let (source, rest) = split_number(text)?;
+    let (line, rest) = split_number(rest.strip_prefix(':')?)?;
+    let (column, rest) = split_number(rest.strip_prefix('(')?)?;
+    let rest = rest.strip_prefix("):")?.trim_start();
+    let (severity, rest) = parse_severity(rest)?;
+    let message = rest.strip_prefix(':')?;
+    Some(diagnostic(severity, source, line, Some(column), message))
+}
+
+// 0(12) : error C0000: message
+fn parse_nvidia(text: &str) -> Option<Diagnostic> {
+    let (source, rest) = split_number(text)?;
+    let (line, rest) = split_number(rest.strip_prefix('(')?)?;
+    let rest = rest.strip_prefix(')')?.trim_start().strip_prefix(':')?;
+    let (severity, rest) = parse_severity(rest.trim_start())?;
+    // skip the error code
+    let message = &rest[rest.find(':')? + 1..];
+    Some(diagnostic(severity, source, line, None, message))
+}
+
+// ERROR: 0:12: message
+fn parse_amd(text: &str) -> Option<Diagnostic> {
+    let (severity, rest) = parse_severity(text)?;
+    let rest = rest.strip_prefix(':')?.trim_start();
+    let (source, rest) = split_number(rest)?;
+    let (line, rest) = split_number(rest.strip_prefix(':')?)?;
+    let message = rest.strip_prefix(':')?;
+    Some(diagnostic(severity, source, line, None, message))
+}
+
+pub fn parse_log(log: &str) -> Vec<Diagnostic> {
+    log.lines()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(|text| {
+            parse_mesa(text)
+                .or_else(|| parse_nvidia(text))
+                .or_else(|| parse_amd(text))
+                .unwrap_or_else(|| Diagnostic {
+                    severity: Severity::Note,
+                    source: None,
+                    file: None,
+                    line: None,
+                    column: None,
+                    message: text.to_string(),
+                    code: None,
+                })
+        })
+        .collect()
+}
+
+// Fills `file` and `code` of the diagnostics from (name, source) pairs
+// indexed by source string number. Empty names are left out.
+pub fn locate(diagnostics: &mut [Diagnostic], files: &[(&str, &str)]) {
+    for diagnostic in diagnostics.iter_mut() {
+        let (name, source) = match diagnostic
+            .source
+            .and_then(|index| files.get(index as usize))
+        {
+            Some(file) => file,
+            None => continue,
+        };
+        if !name.is_empty() {
+            diagnostic.file = Some(name.to_string());
+        }
+        if let Some(line) = diagnostic.line {
+            diagnostic.code = line
+                .checked_sub(1)
+                .and_then(|index| source.lines().nth(index as usize))
+                .map(|code| code.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // severity, source, line, column, message
+    type Expected = (Severity, u32, u32, Option<u32>, &'static str);
+
+    const LOG_LINES: &[(&str, Expected)] = &[
+        (
+            "0:5(12): error: `foo' undeclared",
+            (Severity::Error, 0, 5, Some(12), "`foo' undeclared"),
+        ),
+        (
+            "1:3(8): warning: `x' used uninitialized",
+            (Severity::Warning, 1, 3, Some(8), "`x' used uninitialized"),
+        ),
+        (
+            "0(5) : error C1008: undefined variable \"foo\"",
+            (Severity::Error, 0, 5, None, "undefined variable \"foo\""),
+        ),
+        (
+            "2(7) : warning C7533: global variable gl_FragColor is \
+             deprecated after version 120",
+            (
+                Severity::Warning,
+                2,
+                7,
+                None,
+                "global variable gl_FragColor is deprecated after version 120",
+            ),
+        ),
+        (
+            "ERROR: 0:5: 'foo' : undeclared identifier ",
+            (Severity::Error, 0, 5, None, "'foo' : undeclared identifier"),
+        ),
+        (
+            "WARNING: 1:9: 'z' : variable is unused",
+            (Severity::Warning, 1, 9, None, "'z' : variable is unused"),
+        ),
+    ];
+
+    #[test]
+    fn vendor_log_lines_are_parsed() {
+        for &(text, (severity, source, line, column, message)) in LOG_LINES {
+            assert_eq!(
+                parse_log(text),
+                vec![Diagnostic {
+                    severity,
+                    source: Some(source),
+                    file: None,
+                    line: Some(line),
+                    column,
+                    message: message.to_string(),
+                    code: None,
+                }],
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_lines_become_notes() {
+        let log = "\n0:1(1): error: first\n\
+                   ERROR: 1 compilation errors.  No code generated.\n\n";
+        let diagnostics = parse_log(log);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[1].severity, Severity::Note);
+        assert_eq!(diagnostics[1].line, None);
+        assert_eq!(
+            diagnostics[1].message,
+            "ERROR: 1 compilation errors.  No code generated."
+        );
+    }
+
+    #[test]
+    fn locate_finds_the_file_and_code() {
+        let mut diagnostics = parse_log(
+            "0:2(3): error: a\n1(1) : error C0000: b\nERROR: 2:1: c\n\
+             0:9(1): error: d",
+        );
+        let files = [("main.frag", "void main() {\n  x;\n}"), ("", "float y;")];
+        locate(&mut diagnostics, &files);
+        let located: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.file.as_deref(), d.code.as_deref()))
+            .collect();
+        assert_eq!(
+            located,
+            [
+                (Some("main.frag"), Some("  x;")),
+                // unnamed sources still give the code
+                (None, Some("float y;")),
+                // no such source string
+                (None, None),
+                // past the end of the file
+                (Some("main.frag"), None),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "error at main.frag:2:3: a\n   |\n 2 |   x;\n   |   ^"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use super::diagnostic::Diagnostic;
+use std::fmt;
+use std::os::raw::c_uint;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Geometry,
+    Compute,
+    Other(c_uint),
+}
+
+impl From<c_uint> for ShaderStage {
+    fn from(kind: c_uint) -> Self {
+        match kind {
+            glow::VERTEX_SHADER => ShaderStage::Vertex,
+            glow::FRAGMENT_SHADER => ShaderStage::Fragment,
+            glow::GEOMETRY_SHADER => ShaderStage::Geometry,
+            glow::COMPUTE_SHADER => ShaderStage::Compute,
+            kind => ShaderStage::Other(kind),
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+            ShaderStage::Geometry => write!(f, "geometry"),
+            ShaderStage::Compute => write!(f, "compute"),
+            ShaderStage::Other(kind) => write!(f, "{:#x}", kind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    ContextCreation(String),
+    // the driver refused to create a buffer, texture, program...
+    ObjectCreation(String),
+    ShaderCompile {
+        stage: ShaderStage,
+        log: String,
+        // parsed from the log, with the offending source lines
+        diagnostics: Vec<Diagnostic>,
+    },
+    Link(String),
+    // glGetError value at the time the map returned null
+    BufferMap(u32),
+    MissingUniform(String),
+    // a Uniform handle used with another program than the one it was
+    // taken from, e.g. one kept across a reload
+    ForeignUniform,
+    // an explicit texture unit a texture is already bound to for another
+    // sampler of the program
+    TextureUnitTaken(u32),
+    // every one of the context's texture units is used by another sampler
+    // of the program
+    NoFreeTextureUnit(u32),
+    // the program put a #[location] field of a vertex type elsewhere, it
+    // wasn't built with Program::from_shaders_with_layout
+    AttributeLocation {
+        name: String,
+        found: u32,
+        fixed: u32,
+    },
+    Io {
+        path: PathBuf,
+        reason: String,
+    },
+    Preprocess {
+        file: String,
+        line: u32,
+        reason: String,
+    },
+    // decoding failed or the format is not supported
+    Image(String),
+    // malformed BMFont description, `line` counts from 1
+    Font {
+        line: u32,
+        reason: String,
+    },
+    // byte length of pixel or vertex data doesn't match its dimensions
+    DataSize {
+        expected: usize,
+        found: usize,
+    },
+    // a region that doesn't fit inside a texture of `size`
+    OutOfBounds {
+        region: (u32, u32, u32, u32),
+        size: (u32, u32),
+    },
+    // the operation is not available for this format or backend
+    Unsupported(String),
+    // a non-indexed draw of a vertex array no buffer gave a vertex count
+    NoVertexCount,
+    OutOfMemory,
+    InvalidEnum,
+    InvalidValue,
+    InvalidOperation,
+    // any other glGetError value
+    Gl(u32),
+}
+
+impl Error {
+    pub fn from_gl_error(error: u32) -> Error {
+        match error {
+            glow::OUT_OF_MEMORY => Error::OutOfMemory,
+            glow::INVALID_ENUM => Error::InvalidEnum,
+            glow::INVALID_VALUE => Error::InvalidValue,
+            glow::INVALID_OPERATION => Error::InvalidOperation,
+            error => Error::Gl(error),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ContextCreation(reason) => {
+                write!(f, "cannot create GL context: {}", reason)
+            }
+            Error::ObjectCreation(reason) => {
+                write!(f, "cannot create GL object: {}", reason)
+            }
+            Error::ShaderCompile {
+                stage,
+                log,
+                diagnostics,
+            } => {
+                write!(f, "{} shader compilation failed:", stage)?;
+                if diagnostics.is_empty() {
+                    return write!(f, "\n{}", log);
+                }
+                for diagnostic in diagnostics {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+                Ok(())
+            }
+            Error::Link(log) => write!(f, "program linking failed:\n{}", log),
+            Error::BufferMap(error) => {
+                write!(f, "buffer map is null pointer. Error_number={}", error)
+            }
+            Error::MissingUniform(name) => write!(
+                f,
+                "name \"{}\" does not correspond to an active uniform variable in program or name starts with the reserved prefix \"gl_\"",
+                name
+            ),
+            Error::ForeignUniform => write!(
+                f,
+                "uniform handle belongs to another program, take it from this program with Program::uniform"
+            ),
+            Error::TextureUnitTaken(unit) => write!(
+                f,
+                "texture unit {} is already used by another sampler of the program",
+                unit
+            ),
+            Error::NoFreeTextureUnit(units) => write!(
+                f,
+                "all {} texture units are used by other samplers of the program",
+                units
+            ),
+            Error::AttributeLocation { name, found, fixed } => write!(
+                f,
+                "vertex attribute {} is at location {} in the program but the vertex type fixes it at {}, build the program with Program::from_shaders_with_layout",
+                name, found, fixed
+            ),
+            Error::Io { path, reason } => {
+                write!(f, "cannot read {}: {}", path.display(), reason)
+            }
+            Error::Preprocess { file, line, reason } => {
+                write!(f, "{}:{}: {}", file, line, reason)
+            }
+            Error::Image(reason) => write!(f, "cannot decode image: {}", reason),
+            Error::Font { line, reason } => {
+                write!(f, "cannot parse font, line {}: {}", line, reason)
+            }
+            Error::DataSize { expected, found } => {
+                write!(f, "expected {} bytes of data, got {}", expected, found)
+            }
+            Error::OutOfBounds {
+                region: (x, y, width, height),
+                size: (w, h),
+            } => write!(
+                f,
+                "region {}x{} at ({}, {}) is outside the {}x{} texture",
+                width, height, x, y, w, h
+            ),
+            Error::Unsupported(reason) => write!(f, "unsupported: {}", reason),
+            Error::NoVertexCount => write!(
+                f,
+                "vertex array has no vertex count, wire it with Program::set_layout"
+            ),
+            Error::OutOfMemory => write!(f, "GL is out of memory"),
+            Error::InvalidEnum => write!(f, "invalid enum passed to GL"),
+            Error::InvalidValue => write!(f, "invalid value passed to GL"),
+            Error::InvalidOperation => write!(f, "invalid GL operation"),
+            Error::Gl(error) => write!(f, "GL error {:#x}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
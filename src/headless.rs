@@ -0,0 +1,388 @@
+// Offscreen GL context on top of EGL (surfaceless Mesa / llvmpipe works),
+// so Frame::draw can be exercised without a window.
+use super::{Error, GL};
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_uint, c_void};
+use std::ptr;
+use std::rc::Rc;
+
+type EGLDisplay = *mut c_void;
+type EGLConfig = *mut c_void;
+type EGLSurface = *mut c_void;
+type EGLContext = *mut c_void;
+type EGLint = i32;
+type EGLBoolean = c_uint;
+
+const EGL_FALSE: EGLBoolean = 0;
+const EGL_NONE: EGLint = 0x3038;
+const EGL_ALPHA_SIZE: EGLint = 0x3021;
+const EGL_BLUE_SIZE: EGLint = 0x3022;
+const EGL_GREEN_SIZE: EGLint = 0x3023;
+const EGL_RED_SIZE: EGLint = 0x3024;
+const EGL_DEPTH_SIZE: EGLint = 0x3025;
+const EGL_STENCIL_SIZE: EGLint = 0x3026;
+const EGL_SURFACE_TYPE: EGLint = 0x3033;
+const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+const EGL_HEIGHT: EGLint = 0x3056;
+const EGL_WIDTH: EGLint = 0x3057;
+const EGL_PBUFFER_BIT: EGLint = 0x0001;
+const EGL_OPENGL_BIT: EGLint = 0x0008;
+const EGL_OPENGL_API: c_uint = 0x30A2;
+const EGL_CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+const EGL_CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x0001;
+const EGL_PLATFORM_SURFACELESS_MESA: c_uint = 0x31DD;
+
+type GetProcAddressFn =
+    unsafe extern "system" fn(*const c_char) -> *const c_void;
+type GetPlatformDisplayFn =
+    unsafe extern "system" fn(c_uint, *mut c_void, *const EGLint) -> EGLDisplay;
+type GetDisplayFn = unsafe extern "system" fn(*mut c_void) -> EGLDisplay;
+type InitializeFn = unsafe extern "system" fn(
+    EGLDisplay,
+    *mut EGLint,
+    *mut EGLint,
+) -> EGLBoolean;
+type BindApiFn = unsafe extern "system" fn(c_uint) -> EGLBoolean;
+type ChooseConfigFn = unsafe extern "system" fn(
+    EGLDisplay,
+    *const EGLint,
+    *mut EGLConfig,
+    EGLint,
+    *mut EGLint,
+) -> EGLBoolean;
+type CreatePbufferSurfaceFn = unsafe extern "system" fn(
+    EGLDisplay,
+    EGLConfig,
+    *const EGLint,
+) -> EGLSurface;
+type CreateContextFn = unsafe extern "system" fn(
+    EGLDisplay,
+    EGLConfig,
+    EGLContext,
+    *const EGLint,
+) -> EGLContext;
+type MakeCurrentFn = unsafe extern "system" fn(
+    EGLDisplay,
+    EGLSurface,
+    EGLSurface,
+    EGLContext,
+) -> EGLBoolean;
+type DestroySurfaceFn =
+    unsafe extern "system" fn(EGLDisplay, EGLSurface) -> EGLBoolean;
+type DestroyContextFn =
+    unsafe extern "system" fn(EGLDisplay, EGLContext) -> EGLBoolean;
+type TerminateFn = unsafe extern "system" fn(EGLDisplay) -> EGLBoolean;
+type GetErrorFn = unsafe extern "system" fn() -> EGLint;
+
+struct Egl {
+    lib: *mut c_void,
+    get_proc_address: GetProcAddressFn,
+    get_display: GetDisplayFn,
+    initialize: InitializeFn,
+    bind_api: BindApiFn,
+    choose_config: ChooseConfigFn,
+    create_pbuffer_surface: CreatePbufferSurfaceFn,
+    create_context: CreateContextFn,
+    make_current: MakeCurrentFn,
+    destroy_surface: DestroySurfaceFn,
+    destroy_context: DestroyContextFn,
+    terminate: TerminateFn,
+    get_error: GetErrorFn,
+}
+
+unsafe fn load_symbol(
+    lib: *mut c_void,
+    name: &str,
+) -> Result<*mut c_void, String> {
+    let c_name = CString::new(name).unwrap();
+    let symbol = libc::dlsym(lib, c_name.as_ptr());
+    if symbol.is_null() {
+        return Err(format!("{} is not found in libEGL", name));
+    }
+    Ok(symbol)
+}
+
+impl Egl {
+    unsafe fn load() -> Result<Egl, String> {
+        let mut lib = ptr::null_mut();
+        for name in &["libEGL.so.1", "libEGL.so"] {
+            let c_name = CString::new(*name).unwrap();
+            lib = libc::dlopen(c_name.as_ptr(), libc::RTLD_NOW);
+            if !lib.is_null() {
+                break;
+            }
+        }
+        if lib.is_null() {
+            return Err("cannot load libEGL".to_string());
+        }
+        macro_rules! load {
+            ($name:expr, $ty:ty) => {
+                std::mem::transmute::<*mut c_void, $ty>(load_symbol(
+                    lib, $name,
+                )?)
+            };
+        }
+        Ok(Egl {
+            lib,
+            get_proc_address: load!("eglGetProcAddress", GetProcAddressFn),
+            get_display: load!("eglGetDisplay", GetDisplayFn),
+            initialize: load!("eglInitialize", InitializeFn),
+            bind_api: load!("eglBindAPI", BindApiFn),
+            choose_config: load!("eglChooseConfig", ChooseConfigFn),
+            create_pbuffer_surface: load!(
+                "eglCreatePbufferSurface",
+                CreatePbufferSurfaceFn
+            ),
+            create_context: load!("eglCreateContext", CreateContextFn),
+            make_current: load!("eglMakeCurrent", MakeCurrentFn),
+            destroy_surface: load!("eglDestroySurface", DestroySurfaceFn),
+            destroy_context: load!("eglDestroyContext", DestroyContextFn),
+            terminate: load!("eglTerminate", TerminateFn),
+            get_error: load!("eglGetError", GetErrorFn),
+        })
+    }
+
+    unsafe fn proc_address(&self, name: &str) -> *const c_void {
+        let c_name = CString::new(name).unwrap();
+        (self.get_proc_address)(c_name.as_ptr())
+    }
+
+    unsafe fn error(&self, call: &str) -> Error {
+        Error::ContextCreation(format!(
+            "{} failed. Error_number={:#x}",
+            call,
+            (self.get_error)()
+        ))
+    }
+
+    unsafe fn display(&self) -> EGLDisplay {
+        // prefer the surfaceless platform, it does not need X or a gpu
+        let get_platform_display =
+            self.proc_address("eglGetPlatformDisplayEXT");
+        if !get_platform_display.is_null() {
+            let get_platform_display = std::mem::transmute::<
+                *const c_void,
+                GetPlatformDisplayFn,
+            >(get_platform_display);
+            let display = get_platform_display(
+                EGL_PLATFORM_SURFACELESS_MESA,
+                ptr::null_mut(),
+                ptr::null(),
+            );
+            if !display.is_null() {
+                return display;
+            }
+        }
+        (self.get_display)(ptr::null_mut())
+    }
+}
+
+// EGL objects behind a Headless, shared with every clone of its GL
+struct Context {
+    egl: Egl,
+    display: EGLDisplay,
+    surface: EGLSurface,
+    context: EGLContext,
+}
+
+// Offscreen rgba8 + stencil8 + depth24 framebuffer of a fixed size. The
+// context stays alive as long as this or any clone of its `GL` does.
+pub struct Headless {
+    // a clone holding the context, reads go through its backend
+    gl: GL,
+    width: u32,
+    height: u32,
+}
+
+impl Headless {
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    // Reads back the bound read framebuffer, top row first
+    pub fn read_pixels(&self) -> Result<image::RgbaImage, Error> {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let mut pixels = vec![0u8; w * h * 4];
+        unsafe {
+            self.gl
+                .read_pixels(
+                    0,
+                    0,
+                    w as i32,
+                    h as i32,
+                    glow::RGBA,
+                    glow::UNSIGNED_BYTE,
+                    &mut pixels,
+                )
+                .map_err(Error::Unsupported)?;
+        }
+        // GL origin is bottom left
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for row in pixels.chunks(w * 4).rev() {
+            flipped.extend_from_slice(row);
+        }
+        Ok(image::RgbaImage::from_raw(self.width, self.height, flipped)
+            .expect("pixels are sized for the framebuffer"))
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            (self.egl.make_current)(
+                self.display,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            if !self.context.is_null() {
+                (self.egl.destroy_context)(self.display, self.context);
+            }
+            if !self.surface.is_null() {
+                (self.egl.destroy_surface)(self.display, self.surface);
+            }
+            (self.egl.terminate)(self.display);
+            libc::dlclose(self.egl.lib);
+        }
+    }
+}
+
+impl GL {
+    // Creates an OpenGL 3.3 core context without a window. The context is
+    // made current on the calling thread.
+    pub fn new_headless(
+        width: u32,
+        height: u32,
+    ) -> Result<(GL, Headless), Error> {
+        // EGL takes the size as a positive EGLint
+        let size = |value: u32| i32::try_from(value).ok().filter(|&v| v > 0);
+        let (egl_width, egl_height) = match (size(width), size(height)) {
+            (Some(width), Some(height)) => (width, height),
+            _ => {
+                return Err(Error::ContextCreation(format!(
+                    "invalid headless framebuffer size {}x{}",
+                    width, height
+                )))
+            }
+        };
+        unsafe {
+            let egl = Egl::load().map_err(Error::ContextCreation)?;
+            let display = egl.display();
+            if display.is_null() {
+                return Err(Error::ContextCreation(
+                    "no EGL display available".to_string(),
+                ));
+            }
+            let (mut major, mut minor) = (0, 0);
+            if (egl.initialize)(display, &mut major, &mut minor) == EGL_FALSE {
+                return Err(egl.error("eglInitialize"));
+            }
+            // from here on dropping `headless` releases everything created
+            let mut headless = Context {
+                egl,
+                display,
+                surface: ptr::null_mut(),
+                context: ptr::null_mut(),
+            };
+            let egl = &headless.egl;
+            if (egl.bind_api)(EGL_OPENGL_API) == EGL_FALSE {
+                return Err(egl.error("eglBindAPI"));
+            }
+            let config_attribs = [
+                EGL_SURFACE_TYPE,
+                EGL_PBUFFER_BIT,
+                EGL_RENDERABLE_TYPE,
+                EGL_OPENGL_BIT,
+                EGL_RED_SIZE,
+                8,
+                EGL_GREEN_SIZE,
+                8,
+                EGL_BLUE_SIZE,
+                8,
+                EGL_ALPHA_SIZE,
+                8,
+                EGL_DEPTH_SIZE,
+                24,
+                EGL_STENCIL_SIZE,
+                8,
+                EGL_NONE,
+            ];
+            let mut config = ptr::null_mut();
+            let mut num_configs = 0;
+            if (egl.choose_config)(
+                display,
+                config_attribs.as_ptr(),
+                &mut config,
+                1,
+                &mut num_configs,
+            ) == EGL_FALSE
+                || num_configs == 0
+            {
+                return Err(egl.error("eglChooseConfig"));
+            }
+            let surface_attribs =
+                [EGL_WIDTH, egl_width, EGL_HEIGHT, egl_height, EGL_NONE];
+            let surface = (egl.create_pbuffer_surface)(
+                display,
+                config,
+                surface_attribs.as_ptr(),
+            );
+            if surface.is_null() {
+                return Err(egl.error("eglCreatePbufferSurface"));
+            }
+            headless.surface = surface;
+            let egl = &headless.egl;
+            let context_attribs = [
+                EGL_CONTEXT_MAJOR_VERSION,
+                3,
+                EGL_CONTEXT_MINOR_VERSION,
+                3,
+                EGL_CONTEXT_OPENGL_PROFILE_MASK,
+                EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                EGL_NONE,
+            ];
+            let context = (egl.create_context)(
+                display,
+                config,
+                ptr::null_mut(),
+                context_attribs.as_ptr(),
+            );
+            if context.is_null() {
+                return Err(egl.error("eglCreateContext"));
+            }
+            headless.context = context;
+            let egl = &headless.egl;
+            if (egl.make_current)(display, surface, surface, context)
+                == EGL_FALSE
+            {
+                return Err(egl.error("eglMakeCurrent"));
+            }
+            let gl = GL::from_loader_function(|s| egl.proc_address(s));
+            let gl = gl.with_owner(Rc::new(headless));
+            let headless = Headless {
+                gl: gl.clone(),
+                width,
+                height,
+            };
+            Ok((gl, headless))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_sizes_are_rejected_before_loading_egl() {
+        for &(width, height) in [(0, 1), (1, 0), (1 << 31, 1)].iter() {
+            assert!(matches!(
+                GL::new_headless(width, height),
+                Err(Error::ContextCreation(_))
+            ));
+        }
+    }
+}
@@ -1,48 +1,156 @@
 #[cfg(not(target_arch = "wasm32"))]
 use glow::native::Context as GL_Context;
-use glow::Context;
 use std::ops::Deref;
 use std::rc::Rc;
+// lets the vertex derive, which names `red::`, be used inside the crate
+extern crate self as red;
 extern crate vertex_derive;
 pub use vertex_derive::VertexAttribPointers;
 
+pub mod atlas;
+pub mod backend;
 pub mod buffer;
+pub mod cache;
 pub mod data;
+pub mod diagnostic;
+pub mod error;
+#[cfg(target_os = "linux")]
+pub mod headless;
+pub mod preprocessor;
+pub mod recorder;
+pub mod reflect;
+pub mod reload;
 pub mod shader;
+pub mod text;
+pub mod texture;
 pub use glow;
 
 use std::os::raw::{c_int, c_uint};
 
-pub trait ContextTypes {}
-
 #[derive(Clone, Debug)]
 pub struct GL {
-    inner: Rc<GL_Context>,
+    inner: Rc<StateCache>,
+    // queried once, draws and texture binds check them
+    glsl_version: GlslVersion,
+    samplers: bool,
+    texture_units: u32,
+    // whatever owns the context, e.g. a headless EGL context, kept alive
+    // until the last clone is gone
+    owner: Option<Rc<dyn std::any::Any>>,
 }
 
 impl GL {
+    // Entry points glow doesn't wrap are looked up in the process, see
+    // backend::Native::new
     pub fn new(context: GL_Context) -> GL {
+        GL::from_backend(backend::Native::new(context))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_loader_function<F>(loader_function: F) -> GL
+    where
+        F: FnMut(&str) -> *const std::os::raw::c_void,
+    {
+        GL::from_backend(backend::Native::from_loader_function(loader_function))
+    }
+
+    pub fn from_backend<B: Backend + 'static>(backend: B) -> GL {
+        let inner = StateCache::new(Box::new(backend));
+        let version = unsafe { inner.get_parameter_string(glow::VERSION) };
+        let texture_units = unsafe {
+            inner.get_parameter_i32(glow::MAX_COMBINED_TEXTURE_IMAGE_UNITS)
+        };
         GL {
-            inner: Rc::new(context),
+            inner: Rc::new(inner),
+            glsl_version: GlslVersion::from_gl_version(&version),
+            samplers: GlslVersion::has_samplers(&version),
+            texture_units: texture_units.max(0) as u32,
+            owner: None,
+        }
+    }
+
+    pub(crate) fn with_owner(mut self, owner: Rc<dyn std::any::Any>) -> GL {
+        self.owner = Some(owner);
+        self
+    }
+
+    // The raw glow context for calls red doesn't wrap, None for other
+    // backends. Invalidate the state cache after changing state through it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn native(&self) -> Option<&GL_Context> {
+        self.inner.native()
+    }
+
+    pub fn state_cache(&self) -> &StateCache {
+        &self.inner
+    }
+
+    // Detected from GL_VERSION, see Shader::from_portable_source
+    pub fn glsl_version(&self) -> GlslVersion {
+        self.glsl_version
+    }
+
+    // GL 3.3 and GLES 3 have sampler objects, see texture::Sampler
+    pub fn supports_samplers(&self) -> bool {
+        self.samplers
+    }
+
+    // GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS, the units samplers of a program
+    // are spread over
+    pub fn max_texture_units(&self) -> u32 {
+        self.texture_units
+    }
+
+    pub fn check_error(&self) -> Result<(), Error> {
+        match unsafe { self.inner.get_error() } {
+            glow::NO_ERROR => Ok(()),
+            error => Err(Error::from_gl_error(error)),
         }
     }
 }
 
 impl Deref for GL {
-    type Target = GL_Context;
-    fn deref(&self) -> &GL_Context {
-        &self.inner
+    type Target = dyn Backend;
+    fn deref(&self) -> &(dyn Backend + 'static) {
+        &*self.inner
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Operation {
+    Keep,
+    Zero,
     Replace,
+    Increment,
+    IncrementWrap,
+    Decrement,
+    DecrementWrap,
+    Invert,
 }
 
-#[derive(Debug, Clone)]
+impl Operation {
+    fn op(self) -> u32 {
+        match self {
+            Operation::Keep => glow::KEEP,
+            Operation::Zero => glow::ZERO,
+            Operation::Replace => glow::REPLACE,
+            Operation::Increment => glow::INCR,
+            Operation::IncrementWrap => glow::INCR_WRAP,
+            Operation::Decrement => glow::DECR,
+            Operation::DecrementWrap => glow::DECR_WRAP,
+            Operation::Invert => glow::INVERT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum StencilTest {
     AlwaysPass,
+    Never,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
     Equal,
     NotEqual,
 }
@@ -53,22 +161,214 @@ impl Default for StencilTest {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+impl StencilTest {
+    fn func(self) -> u32 {
+        match self {
+            StencilTest::AlwaysPass => glow::ALWAYS,
+            StencilTest::Never => glow::NEVER,
+            StencilTest::Less => glow::LESS,
+            StencilTest::LessOrEqual => glow::LEQUAL,
+            StencilTest::Greater => glow::GREATER,
+            StencilTest::GreaterOrEqual => glow::GEQUAL,
+            StencilTest::Equal => glow::EQUAL,
+            StencilTest::NotEqual => glow::NOTEQUAL,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Stencil {
+    // mask applied to both ref_value and the stored value before the test
     pub mask: c_uint,
+    // bits of the stencil buffer the operations are allowed to modify
+    pub write_mask: c_uint,
     pub ref_value: c_int,
     pub test: StencilTest,
+    // None keeps the stored value
+    pub fail_operation: Option<Operation>,
+    pub depth_fail_operation: Option<Operation>,
     pub pass_operation: Option<Operation>,
 }
 
+impl Default for Stencil {
+    fn default() -> Self {
+        Self {
+            mask: 0,
+            write_mask: 0xFF,
+            ref_value: 0,
+            test: StencilTest::default(),
+            fail_operation: None,
+            depth_fail_operation: None,
+            pass_operation: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DepthTest {
+    AlwaysPass,
+    Never,
+    #[default]
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl DepthTest {
+    fn func(self) -> u32 {
+        match self {
+            DepthTest::AlwaysPass => glow::ALWAYS,
+            DepthTest::Never => glow::NEVER,
+            DepthTest::Less => glow::LESS,
+            DepthTest::LessOrEqual => glow::LEQUAL,
+            DepthTest::Greater => glow::GREATER,
+            DepthTest::GreaterOrEqual => glow::GEQUAL,
+            DepthTest::Equal => glow::EQUAL,
+            DepthTest::NotEqual => glow::NOTEQUAL,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Depth {
+    pub test: DepthTest,
+    pub write: bool,
+    // (near, far) mapping of normalized device depth to window depth
+    pub range: (f32, f32),
+    // (factor, units), applied to filled polygons
+    pub polygon_offset: Option<(f32, f32)>,
+}
+
+impl Default for Depth {
+    fn default() -> Self {
+        Self {
+            test: DepthTest::default(),
+            write: true,
+            range: (0.0, 1.0),
+            polygon_offset: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum DrawType {
     Standart,
     Instancing(usize),
 }
 
-#[derive(Debug, Default)]
-pub struct Blend;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+}
+
+impl BlendFactor {
+    fn factor(self) -> u32 {
+        match self {
+            BlendFactor::Zero => glow::ZERO,
+            BlendFactor::One => glow::ONE,
+            BlendFactor::SrcColor => glow::SRC_COLOR,
+            BlendFactor::OneMinusSrcColor => glow::ONE_MINUS_SRC_COLOR,
+            BlendFactor::DstColor => glow::DST_COLOR,
+            BlendFactor::OneMinusDstColor => glow::ONE_MINUS_DST_COLOR,
+            BlendFactor::SrcAlpha => glow::SRC_ALPHA,
+            BlendFactor::OneMinusSrcAlpha => glow::ONE_MINUS_SRC_ALPHA,
+            BlendFactor::DstAlpha => glow::DST_ALPHA,
+            BlendFactor::OneMinusDstAlpha => glow::ONE_MINUS_DST_ALPHA,
+            BlendFactor::ConstantColor => glow::CONSTANT_COLOR,
+            BlendFactor::OneMinusConstantColor => {
+                glow::ONE_MINUS_CONSTANT_COLOR
+            }
+            BlendFactor::ConstantAlpha => glow::CONSTANT_ALPHA,
+            BlendFactor::OneMinusConstantAlpha => {
+                glow::ONE_MINUS_CONSTANT_ALPHA
+            }
+            BlendFactor::SrcAlphaSaturate => glow::SRC_ALPHA_SATURATE,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BlendEquation {
+    #[default]
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendEquation {
+    fn equation(self) -> u32 {
+        match self {
+            BlendEquation::Add => glow::FUNC_ADD,
+            BlendEquation::Subtract => glow::FUNC_SUBTRACT,
+            BlendEquation::ReverseSubtract => glow::FUNC_REVERSE_SUBTRACT,
+            BlendEquation::Min => glow::MIN,
+            BlendEquation::Max => glow::MAX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blend {
+    pub src_rgb: BlendFactor,
+    pub dst_rgb: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+    pub equation_rgb: BlendEquation,
+    pub equation_alpha: BlendEquation,
+    // used by the Constant* factors
+    pub color: (f32, f32, f32, f32),
+}
+
+impl Blend {
+    pub fn new(src: BlendFactor, dst: BlendFactor) -> Blend {
+        Blend {
+            src_rgb: src,
+            dst_rgb: dst,
+            src_alpha: src,
+            dst_alpha: dst,
+            equation_rgb: BlendEquation::default(),
+            equation_alpha: BlendEquation::default(),
+            color: (0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn alpha() -> Blend {
+        Blend::new(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    pub fn additive() -> Blend {
+        Blend::new(BlendFactor::SrcAlpha, BlendFactor::One)
+    }
+
+    pub fn premultiplied_alpha() -> Blend {
+        Blend::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+    }
+}
+
+impl Default for Blend {
+    fn default() -> Self {
+        Blend::alpha()
+    }
+}
 
 impl Default for DrawType {
     fn default() -> Self {
@@ -76,10 +376,57 @@ impl Default for DrawType {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    // sized by DrawParams::point_size
+    Points,
+    Lines,
+    LineStrip,
+    LineLoop,
+    #[default]
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+impl Primitive {
+    fn mode(self) -> u32 {
+        match self {
+            Primitive::Points => glow::POINTS,
+            Primitive::Lines => glow::LINES,
+            Primitive::LineStrip => glow::LINE_STRIP,
+            Primitive::LineLoop => glow::LINE_LOOP,
+            Primitive::Triangles => glow::TRIANGLES,
+            Primitive::TriangleStrip => glow::TRIANGLE_STRIP,
+            Primitive::TriangleFan => glow::TRIANGLE_FAN,
+        }
+    }
+
+    fn is_line(self) -> bool {
+        matches!(
+            self,
+            Primitive::Lines | Primitive::LineStrip | Primitive::LineLoop
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct DrawParams {
     pub stencil: Option<Stencil>,
+    // if set, `stencil` only applies to front faces and this to back faces
+    pub back_stencil: Option<Stencil>,
+    // None disables the depth test
+    pub depth: Option<Depth>,
     pub draw_type: DrawType,
+    pub primitive: Primitive,
+    // only used for line primitives. Core profile drivers may only support
+    // 1.0 and forward compatible contexts reject wider lines with
+    // GL_INVALID_VALUE, draw quads for thick lines.
+    pub line_width: f32,
+    // only used for Points. None takes gl_PointSize written by the vertex
+    // shader, Some gives every point that size like glPointSize. GLES
+    // always uses gl_PointSize.
+    pub point_size: Option<f32>,
     pub color_mask: (bool, bool, bool, bool),
     pub blend: Option<Blend>,
 }
@@ -88,9 +435,14 @@ impl Default for DrawParams {
     fn default() -> Self {
         Self {
             stencil: None,
+            back_stencil: None,
+            depth: None,
             draw_type: DrawType::default(),
+            primitive: Primitive::default(),
+            line_width: 1.0,
+            point_size: None,
             color_mask: (true, true, true, true),
-            blend: Some(Blend),
+            blend: Some(Blend::default()),
         }
     }
 }
@@ -113,20 +465,32 @@ impl Frame {
         index_buffer: Option<&buffer::IndexBuffer>,
         program: &Program,
         draw_params: &DrawParams,
-    ) {
+    ) -> Result<(), Error> {
+        // checked before any state changes, indexed draws count the indices
+        let vertex_count = match index_buffer {
+            Some(_) => 0,
+            None => vao.vertex_count().ok_or(Error::NoVertexCount)?,
+        };
         vao.bind();
         if let Some(blend) = &draw_params.blend {
             unsafe {
-                // TODO make as param
                 self.gl.enable(glow::BLEND);
-                self.gl
-                    .blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
-                // self.gl.enable(glow::STENCIL_TEST); // TODO: Should it be here?
+                self.gl.blend_func_separate(
+                    blend.src_rgb.factor(),
+                    blend.dst_rgb.factor(),
+                    blend.src_alpha.factor(),
+                    blend.dst_alpha.factor(),
+                );
+                self.gl.blend_equation_separate(
+                    blend.equation_rgb.equation(),
+                    blend.equation_alpha.equation(),
+                );
+                let (r, g, b, a) = blend.color;
+                self.gl.blend_color(r, g, b, a);
             }
         } else {
             unsafe {
-                // strange
-                // self.gl.disable(glow::BLEND);
+                self.gl.disable(glow::BLEND);
             }
         }
         unsafe {
@@ -137,58 +501,54 @@ impl Frame {
                 draw_params.color_mask.3,
             );
         }
-        if let Some(stencil) = draw_params.stencil.clone() {
-            unsafe {
-                self.gl.stencil_mask(0xFF);
-            }
-            match stencil.test {
-                StencilTest::AlwaysPass => unsafe {
-                    self.gl.stencil_func(
-                        glow::ALWAYS,
-                        stencil.ref_value,
-                        stencil.mask,
-                    );
-                },
-                StencilTest::Equal => unsafe {
-                    self.gl.stencil_func(
-                        glow::EQUAL,
-                        stencil.ref_value,
-                        stencil.mask,
-                    )
-                },
-                StencilTest::NotEqual => unsafe {
-                    self.gl.stencil_func(
-                        glow::NOTEQUAL,
-                        stencil.ref_value,
-                        stencil.mask,
-                    )
-                },
-            }
-            let mut pass_operation = glow::KEEP;
-            let fail_operation = glow::KEEP;
-            let depth_fail_operation = glow::KEEP;
-            if let Some(operation) = stencil.pass_operation {
-                match operation {
-                    Operation::Replace => {
-                        pass_operation = glow::REPLACE;
-                    }
-                }
+        match &draw_params.back_stencil {
+            Some(back_stencil) => {
+                self.set_stencil(glow::FRONT, draw_params.stencil.as_ref());
+                self.set_stencil(glow::BACK, Some(back_stencil));
             }
+            None => self.set_stencil(
+                glow::FRONT_AND_BACK,
+                draw_params.stencil.as_ref(),
+            ),
+        }
+        if let Some(depth) = &draw_params.depth {
             unsafe {
-                self.gl.stencil_op(
-                    fail_operation,
-                    depth_fail_operation,
-                    pass_operation,
-                )
+                self.gl.enable(glow::DEPTH_TEST);
+                self.gl.depth_func(depth.test.func());
+                self.gl.depth_mask(depth.write);
+                self.gl.depth_range_f32(depth.range.0, depth.range.1);
+                if let Some((factor, units)) = depth.polygon_offset {
+                    self.gl.enable(glow::POLYGON_OFFSET_FILL);
+                    self.gl.polygon_offset(factor, units);
+                } else {
+                    self.gl.disable(glow::POLYGON_OFFSET_FILL);
+                }
             }
         } else {
             unsafe {
-                self.gl.stencil_mask(0x00);
-                self.gl.stencil_func(glow::ALWAYS, 0, 0xFF);
-                // TODO: just deltete?
-                self.gl.stencil_op(glow::KEEP, glow::KEEP, glow::KEEP)
+                self.gl.disable(glow::DEPTH_TEST);
+                self.gl.disable(glow::POLYGON_OFFSET_FILL);
             }
-        };
+        }
+        let mode = draw_params.primitive.mode();
+        unsafe {
+            if draw_params.primitive.is_line() {
+                self.gl.line_width(draw_params.line_width);
+            }
+            // GLES has no fixed point size to switch to
+            if self.gl.glsl_version() == GlslVersion::Glsl330 {
+                match (draw_params.primitive, draw_params.point_size) {
+                    (Primitive::Points, Some(size)) => {
+                        self.gl.disable(glow::PROGRAM_POINT_SIZE);
+                        self.gl.point_size(size);
+                    }
+                    (Primitive::Points, None) => {
+                        self.gl.enable(glow::PROGRAM_POINT_SIZE)
+                    }
+                    _ => self.gl.disable(glow::PROGRAM_POINT_SIZE),
+                }
+            }
+        }
         program.set_used();
         unsafe {
             match index_buffer {
@@ -197,7 +557,7 @@ impl Frame {
                     match draw_params.draw_type {
                         DrawType::Standart => {
                             self.gl.draw_elements(
-                                glow::TRIANGLES,
+                                mode,
                                 index_buffer.size as i32,
                                 glow::UNSIGNED_SHORT,
                                 0,
@@ -205,7 +565,7 @@ impl Frame {
                         }
                         DrawType::Instancing(instance_count) => {
                             self.gl.draw_elements_instanced(
-                                glow::TRIANGLES,
+                                mode,
                                 index_buffer.size as i32,
                                 glow::UNSIGNED_SHORT,
                                 0,
@@ -214,13 +574,55 @@ impl Frame {
                         }
                     }
                 }
-                None => {
-                    unimplemented!();
-                }
+                None => match draw_params.draw_type {
+                    DrawType::Standart => {
+                        self.gl.draw_arrays(mode, 0, vertex_count as i32);
+                    }
+                    DrawType::Instancing(instance_count) => {
+                        self.gl.draw_arrays_instanced(
+                            mode,
+                            0,
+                            vertex_count as i32,
+                            instance_count as i32,
+                        )
+                    }
+                },
             }
-            self.gl.stencil_mask(0xFF); // oh it's so painfull
         }
-        vao.unbind();
+        Ok(())
+    }
+
+    fn set_stencil(&self, face: u32, stencil: Option<&Stencil>) {
+        match stencil {
+            Some(stencil) => unsafe {
+                let op = |operation: Option<Operation>| {
+                    operation.unwrap_or(Operation::Keep).op()
+                };
+                self.gl.stencil_mask_separate(face, stencil.write_mask);
+                self.gl.stencil_func_separate(
+                    face,
+                    stencil.test.func(),
+                    stencil.ref_value,
+                    stencil.mask,
+                );
+                self.gl.stencil_op_separate(
+                    face,
+                    op(stencil.fail_operation),
+                    op(stencil.depth_fail_operation),
+                    op(stencil.pass_operation),
+                );
+            },
+            None => unsafe {
+                self.gl.stencil_mask_separate(face, 0x00);
+                self.gl.stencil_func_separate(face, glow::ALWAYS, 0, 0xFF);
+                self.gl.stencil_op_separate(
+                    face,
+                    glow::KEEP,
+                    glow::KEEP,
+                    glow::KEEP,
+                );
+            },
+        }
     }
     pub fn set_clear_stencil(&self, stencil: i32) {
         unsafe { self.gl.clear_stencil(stencil) };
@@ -231,18 +633,51 @@ impl Frame {
     }
 
     pub fn clear_color(&self) {
-        unsafe { self.gl.clear(glow::COLOR_BUFFER_BIT) };
+        self.clear(glow::COLOR_BUFFER_BIT);
     }
 
     pub fn clear_stencil(&self) {
-        unsafe { self.gl.clear(glow::STENCIL_BUFFER_BIT) };
+        self.clear(glow::STENCIL_BUFFER_BIT);
     }
 
     pub fn clear_color_and_stencil(&self) {
+        self.clear(glow::COLOR_BUFFER_BIT | glow::STENCIL_BUFFER_BIT);
+    }
+
+    pub fn set_clear_depth(&self, depth: f32) {
+        unsafe { self.gl.clear_depth_f32(depth) };
+    }
+
+    pub fn clear_depth(&self) {
+        self.clear(glow::DEPTH_BUFFER_BIT);
+    }
+
+    pub fn clear_color_and_depth(&self) {
+        self.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+    }
+
+    pub fn clear_all(&self) {
+        self.clear(
+            glow::COLOR_BUFFER_BIT
+                | glow::DEPTH_BUFFER_BIT
+                | glow::STENCIL_BUFFER_BIT,
+        );
+    }
+
+    // the last draw may have left write masks off, and glClear respects them
+    fn clear(&self, mask: u32) {
         unsafe {
-            self.gl
-                .clear(glow::COLOR_BUFFER_BIT | glow::STENCIL_BUFFER_BIT)
-        };
+            if mask & glow::COLOR_BUFFER_BIT != 0 {
+                self.gl.color_mask(true, true, true, true);
+            }
+            if mask & glow::DEPTH_BUFFER_BIT != 0 {
+                self.gl.depth_mask(true);
+            }
+            if mask & glow::STENCIL_BUFFER_BIT != 0 {
+                self.gl.stencil_mask(0xFF);
+            }
+            self.gl.clear(mask);
+        }
     }
 }
 
@@ -274,5 +709,286 @@ impl Viewport {
     }
 }
 
+pub use atlas::{AtlasSprite, TextureAtlas, UvRect};
+pub use backend::Backend;
+pub use cache::{CacheStats, StateCache};
 pub use data::*;
+pub use diagnostic::{Diagnostic, Severity};
+pub use error::{Error, ShaderStage};
+#[cfg(target_os = "linux")]
+pub use headless::Headless;
+pub use preprocessor::Preprocessor;
+pub use reflect::{
+    AttributeInfo, BaseType, GlslType, Mismatch, UniformInfo, VertexReport,
+};
+pub use reload::{ProgramBuilder, ReloadableProgram};
 pub use shader::*;
+pub use text::{Align, BitmapFont, GlyphCache, TextRenderer, TextStyle};
+pub use texture::{
+    Filter, ImageOptions, LoadedImage, SampledTexture, Sampler, Sampling,
+    Texture, TextureBuilder, TextureFormat, TextureUnit, Wrap,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use recorder::{Event, Recorder};
+
+    #[derive(VertexAttribPointers, Copy, Clone)]
+    #[repr(C, packed)]
+    struct Vertex {
+        position: f32_f32,
+    }
+
+    fn triangle(recorder: &Recorder) -> (GL, Program, VertexBuffer<Vertex>) {
+        let gl = GL::from_backend(recorder.clone());
+        let shader = Shader::from_vert_source(&gl, "").unwrap();
+        let program = Program::from_shaders(&gl, &[shader]).unwrap();
+        let vertices = [Vertex {
+            position: (0.0, 0.0).into(),
+        }; 3];
+        let buffer = VertexBuffer::new(&gl, &vertices).unwrap();
+        program.set_layout(&gl, &buffer.vao, &[&buffer]).unwrap();
+        (gl, program, buffer)
+    }
+
+    #[test]
+    fn only_native_backends_expose_a_context() {
+        let gl = GL::from_backend(Recorder::new());
+        assert!(gl.native().is_none());
+    }
+
+    #[test]
+    fn repeated_draw_only_issues_the_draw_call() {
+        let recorder = Recorder::new();
+        let (gl, program, buffer) = triangle(&recorder);
+        let frame = Frame::new(&gl);
+        let params = DrawParams::default();
+        frame.draw(&buffer.vao, None, &program, &params).unwrap();
+        recorder.clear();
+        frame.draw(&buffer.vao, None, &program, &params).unwrap();
+        assert_eq!(
+            recorder.events(),
+            vec![Event::DrawArrays {
+                mode: glow::TRIANGLES,
+                first: 0,
+                count: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn set_layout_gives_the_vertex_array_a_count() {
+        let recorder = Recorder::new();
+        let (gl, program, buffer) = triangle(&recorder);
+        let vao = buffer::VertexArray::new(&gl).unwrap();
+        let frame = Frame::new(&gl);
+        let params = DrawParams::default();
+        assert!(matches!(
+            frame.draw(&vao, None, &program, &params),
+            Err(Error::NoVertexCount)
+        ));
+        program.set_layout(&gl, &vao, &[&buffer]).unwrap();
+        frame.draw(&vao, None, &program, &params).unwrap();
+        assert_eq!(
+            recorder.events().last(),
+            Some(&Event::DrawArrays {
+                mode: glow::TRIANGLES,
+                first: 0,
+                count: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn vertex_count_follows_uploads_after_set_layout() {
+        let recorder = Recorder::new();
+        let (gl, program, _) = triangle(&recorder);
+        let mut buffer = VertexBuffer::empty_new(&gl).unwrap();
+        let vao = buffer::VertexArray::new(&gl).unwrap();
+        program.set_layout(&gl, &vao, &[&buffer]).unwrap();
+        let vertices = [Vertex {
+            position: (0.0, 0.0).into(),
+        }; 6];
+        buffer.dynamic_draw_data(&vertices);
+        let frame = Frame::new(&gl);
+        let params = DrawParams::default();
+        for buffer_vao in [&vao, &buffer.vao].iter() {
+            frame.draw(buffer_vao, None, &program, &params).unwrap();
+            assert_eq!(
+                recorder.events().last(),
+                Some(&Event::DrawArrays {
+                    mode: glow::TRIANGLES,
+                    first: 0,
+                    count: 6,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn index_buffers_leave_the_drawn_vertex_array_alone() {
+        let recorder = Recorder::new();
+        let (gl, program, buffer) = triangle(&recorder);
+        let frame = Frame::new(&gl);
+        frame
+            .draw(&buffer.vao, None, &program, &DrawParams::default())
+            .unwrap();
+        recorder.clear();
+        buffer::IndexBuffer::new(&gl, &[0, 1, 2]).unwrap();
+        let events = recorder.events();
+        let unbound = events
+            .iter()
+            .position(|e| *e == Event::BindVertexArray { vertex_array: None })
+            .unwrap();
+        let element_bound = events
+            .iter()
+            .position(|e| match e {
+                Event::BindBuffer { target, .. } => {
+                    *target == glow::ELEMENT_ARRAY_BUFFER
+                }
+                _ => false,
+            })
+            .unwrap();
+        assert!(unbound < element_bound);
+    }
+
+    #[test]
+    fn draw_params_set_blend_and_depth_state() {
+        let recorder = Recorder::new();
+        let (gl, program, buffer) = triangle(&recorder);
+        let frame = Frame::new(&gl);
+        recorder.clear();
+        let params = DrawParams {
+            blend: Some(Blend::alpha()),
+            depth: Some(Depth {
+                test: DepthTest::LessOrEqual,
+                write: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        frame.draw(&buffer.vao, None, &program, &params).unwrap();
+        let events = recorder.events();
+        for expected in &[
+            Event::Enable {
+                parameter: glow::BLEND,
+            },
+            Event::BlendFuncSeparate {
+                src_rgb: glow::SRC_ALPHA,
+                dst_rgb: glow::ONE_MINUS_SRC_ALPHA,
+                src_alpha: glow::SRC_ALPHA,
+                dst_alpha: glow::ONE_MINUS_SRC_ALPHA,
+            },
+            Event::Enable {
+                parameter: glow::DEPTH_TEST,
+            },
+            Event::DepthFunc { func: glow::LEQUAL },
+            Event::DepthMask { value: false },
+            Event::Disable {
+                parameter: glow::POLYGON_OFFSET_FILL,
+            },
+        ] {
+            assert!(events.contains(expected), "missing {:?}", expected);
+        }
+        assert_eq!(
+            events.last(),
+            Some(&Event::DrawArrays {
+                mode: glow::TRIANGLES,
+                first: 0,
+                count: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn point_size_is_only_enabled_for_points() {
+        let recorder = Recorder::new();
+        let (gl, program, buffer) = triangle(&recorder);
+        let frame = Frame::new(&gl);
+        let point_events = |params: DrawParams| {
+            recorder.clear();
+            frame.draw(&buffer.vao, None, &program, &params).unwrap();
+            recorder
+                .events()
+                .into_iter()
+                .filter(|event| match event {
+                    Event::Enable { parameter }
+                    | Event::Disable { parameter } => {
+                        *parameter == glow::PROGRAM_POINT_SIZE
+                    }
+                    Event::PointSize { .. } => true,
+                    _ => false,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            point_events(DrawParams {
+                primitive: Primitive::Points,
+                ..Default::default()
+            }),
+            [Event::Enable {
+                parameter: glow::PROGRAM_POINT_SIZE,
+            }]
+        );
+        assert_eq!(
+            point_events(DrawParams {
+                primitive: Primitive::Points,
+                point_size: Some(4.0),
+                ..Default::default()
+            }),
+            [
+                Event::Disable {
+                    parameter: glow::PROGRAM_POINT_SIZE,
+                },
+                Event::PointSize { size: 4.0 },
+            ]
+        );
+        point_events(DrawParams {
+            primitive: Primitive::Points,
+            ..Default::default()
+        });
+        assert_eq!(
+            point_events(DrawParams::default()),
+            [Event::Disable {
+                parameter: glow::PROGRAM_POINT_SIZE,
+            }]
+        );
+    }
+
+    #[test]
+    fn clear_restores_write_masks() {
+        let recorder = Recorder::new();
+        let (gl, program, buffer) = triangle(&recorder);
+        let frame = Frame::new(&gl);
+        let params = DrawParams {
+            color_mask: (false, false, false, false),
+            depth: Some(Depth {
+                write: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        frame.draw(&buffer.vao, None, &program, &params).unwrap();
+        recorder.clear();
+        frame.clear_all();
+        assert_eq!(
+            recorder.events(),
+            vec![
+                Event::ColorMask {
+                    red: true,
+                    green: true,
+                    blue: true,
+                    alpha: true,
+                },
+                Event::DepthMask { value: true },
+                Event::StencilMask { mask: 0xFF },
+                Event::Clear {
+                    mask: glow::COLOR_BUFFER_BIT
+                        | glow::DEPTH_BUFFER_BIT
+                        | glow::STENCIL_BUFFER_BIT,
+                },
+            ]
+        );
+    }
+}
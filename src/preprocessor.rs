@@ -0,0 +1,501 @@
+// GLSL preprocessing done before the source reaches the driver:
+// `#include "name"` from an in-memory registry or include directories,
+// injected `#define`s and `#line` directives so driver errors still point
+// to the right file (the file index is the GLSL source string number).
+use super::shader::GlslVersion;
+use super::Error;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+    // set for files read from disk
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Processed {
+    pub source: String,
+    // indexed by the source string number used in #line
+    pub files: Vec<SourceFile>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    files: HashMap<String, String>,
+    include_dirs: Vec<PathBuf>,
+    defines: Vec<(String, String)>,
+    version: Option<GlslVersion>,
+}
+
+fn include_name(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix("include")?.trim();
+    let name = rest
+        .strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .or_else(|| rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')))?;
+    Some(name)
+}
+
+// `#pragma once`, not other pragmas that happen to contain "once"
+fn is_pragma_once(line: &str) -> bool {
+    let rest = match line.trim().strip_prefix('#') {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut tokens = rest.split_whitespace();
+    tokens.next() == Some("pragma")
+        && tokens.next() == Some("once")
+        && tokens.next().is_none_or(|token| token.starts_with("//"))
+}
+
+// Whether `#line N` makes the next line N + 1 instead of N, as it does
+// before GLSL 3.30 and in GLSL ES 1.00. `version` is the #version line,
+// without one the source is GLSL 1.10.
+fn counts_line_after(version: Option<&str>) -> bool {
+    let mut tokens = match version {
+        Some(line) => line.trim().trim_start_matches('#').split_whitespace(),
+        None => return true,
+    };
+    let number: u32 = match tokens.nth(1).and_then(|n| n.parse().ok()) {
+        Some(number) => number,
+        None => return true,
+    };
+    let es = tokens.next() == Some("es");
+    number < 300 || (!es && number < 330)
+}
+
+// Whether `line` is blank or only comments, which may come before
+// #version. `in_comment` tracks /* */ blocks across lines.
+fn is_comment_only(line: &str, in_comment: &mut bool) -> bool {
+    let mut rest = line.trim();
+    loop {
+        if *in_comment {
+            match rest.find("*/") {
+                Some(end) => {
+                    *in_comment = false;
+                    rest = rest[end + 2..].trim_start();
+                }
+                None => return true,
+            }
+        }
+        if rest.is_empty() || rest.starts_with("//") {
+            return true;
+        }
+        match rest.strip_prefix("/*") {
+            Some(comment) => {
+                *in_comment = true;
+                rest = comment;
+            }
+            None => return false,
+        }
+    }
+}
+
+fn is_directive(line: &str, directive: &str) -> bool {
+    line.trim()
+        .strip_prefix('#')
+        .map(|rest| rest.trim_start().starts_with(directive))
+        .unwrap_or(false)
+}
+
+impl Preprocessor {
+    pub fn new() -> Preprocessor {
+        Preprocessor::default()
+    }
+
+    pub fn add_file<N: Into<String>, S: Into<String>>(
+        &mut self,
+        name: N,
+        source: S,
+    ) -> &mut Self {
+        self.files.insert(name.into(), source.into());
+        self
+    }
+
+    // searched in order when the registry doesn't have the file
+    pub fn include_dir<P: Into<PathBuf>>(&mut self, path: P) -> &mut Self {
+        self.include_dirs.push(path.into());
+        self
+    }
+
+    pub fn define<N: Into<String>, V: Into<String>>(
+        &mut self,
+        name: N,
+        value: V,
+    ) -> &mut Self {
+        self.defines.push((name.into(), value.into()));
+        self
+    }
+
+    // Sources without their own #version get the header of `version`, see
+    // Shader::from_portable_source
+    pub fn version(&mut self, version: GlslVersion) -> &mut Self {
+        self.version = Some(version);
+        self
+    }
+
+    fn load(&self, name: &str) -> Option<SourceFile> {
+        if let Some(source) = self.files.get(name) {
+            return Some(SourceFile {
+                name: name.to_string(),
+                source: source.clone(),
+                path: None,
+            });
+        }
+        self.include_dirs.iter().find_map(|dir| {
+            let path = dir.join(name);
+            let source = std::fs::read_to_string(&path).ok()?;
+            Some(SourceFile {
+                name: name.to_string(),
+                source,
+                path: Some(path),
+            })
+        })
+    }
+
+    // Where `name` was found on disk, None for files from the registry
+    pub(crate) fn path_of(&self, name: &str) -> Option<PathBuf> {
+        self.load(name).and_then(|file| file.path)
+    }
+
+    // Processes a file from the registry or the include directories
+    pub fn process_file(&self, name: &str) -> Result<Processed, Error> {
+        let file = self.load(name).ok_or_else(|| Error::Preprocess {
+            file: name.to_string(),
+            line: 0,
+            reason: "file not found".to_string(),
+        })?;
+        self.process_source_file(file)
+    }
+
+    pub fn process(
+        &self,
+        name: &str,
+        source: &str,
+    ) -> Result<Processed, Error> {
+        self.process_source_file(SourceFile {
+            name: name.to_string(),
+            source: source.to_string(),
+            path: None,
+        })
+    }
+
+    fn process_source_file(
+        &self,
+        file: SourceFile,
+    ) -> Result<Processed, Error> {
+        let mut output = String::new();
+        let mut lines = file.source.lines().enumerate().peekable();
+        // #version has to stay the very first thing
+        let mut next_line = 1;
+        let mut version = None;
+        let mut in_comment = false;
+        while let Some((_, line)) = lines.peek() {
+            if is_comment_only(line, &mut in_comment) {
+                output.push_str(line);
+                output.push('\n');
+                lines.next();
+                next_line += 1;
+                continue;
+            }
+            if is_directive(line, "version") {
+                version = Some(line.to_string());
+                output.push_str(line);
+                output.push('\n');
+                lines.next();
+                next_line += 1;
+            }
+            break;
+        }
+        let line_after = match (&version, self.version) {
+            (None, Some(header)) => {
+                output.push_str(header.header());
+                header == GlslVersion::Es100
+            }
+            (version, _) => counts_line_after(version.as_deref()),
+        };
+        let line_base = if line_after { 1 } else { 0 };
+        for (name, value) in self.defines.iter() {
+            output.push_str(&format!("#define {} {}\n", name, value));
+        }
+        output.push_str(&format!("#line {} 0\n", next_line - line_base));
+        let rest: Vec<&str> = lines.map(|(_, line)| line).collect();
+        let mut processed = Processed {
+            source: output,
+            files: vec![file.clone()],
+        };
+        let mut stack = vec![file.name.clone()];
+        self.expand(
+            &mut processed,
+            &rest,
+            0,
+            next_line,
+            line_base,
+            &mut stack,
+        )?;
+        Ok(processed)
+    }
+
+    fn expand(
+        &self,
+        processed: &mut Processed,
+        lines: &[&str],
+        file_index: usize,
+        first_line: usize,
+        // subtracted from #line numbers, see counts_line_after
+        line_base: usize,
+        stack: &mut Vec<String>,
+    ) -> Result<(), Error> {
+        for (offset, line) in lines.iter().enumerate() {
+            let line_number = first_line + offset;
+            if is_pragma_once(line) {
+                // handled when including, keep the numbering intact
+                processed.source.push('\n');
+                continue;
+            }
+            let name = match include_name(line) {
+                Some(name) => name,
+                None => {
+                    processed.source.push_str(line);
+                    processed.source.push('\n');
+                    continue;
+                }
+            };
+            let error = |reason: &str| Error::Preprocess {
+                file: stack.last().cloned().unwrap_or_default(),
+                line: line_number as u32,
+                reason: reason.to_string(),
+            };
+            if stack.iter().any(|included| included == name) {
+                return Err(error(&format!(
+                    "recursive include of \"{}\"",
+                    name
+                )));
+            }
+            let existing = processed.files.iter().position(|f| f.name == name);
+            let file = match existing {
+                Some(index) => processed.files[index].clone(),
+                None => self.load(name).ok_or_else(|| {
+                    error(&format!("cannot include \"{}\"", name))
+                })?,
+            };
+            let once = file.source.lines().any(is_pragma_once);
+            if existing.is_some() && once {
+                processed.source.push('\n');
+                continue;
+            }
+            let index = existing.unwrap_or_else(|| {
+                processed.files.push(file.clone());
+                processed.files.len() - 1
+            });
+            processed.source.push_str(&format!(
+                "#line {} {}\n",
+                1 - line_base,
+                index
+            ));
+            let included: Vec<&str> = file.source.lines().collect();
+            stack.push(name.to_string());
+            self.expand(processed, &included, index, 1, line_base, stack)?;
+            stack.pop();
+            processed.source.push_str(&format!(
+                "#line {} {}\n",
+                line_number + 1 - line_base,
+                file_index
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preprocess_error(result: Result<Processed, Error>) -> (String, u32) {
+        match result {
+            Err(Error::Preprocess { file, line, .. }) => (file, line),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn includes_are_wrapped_in_line_directives() {
+        let mut preprocessor = Preprocessor::new();
+        preprocessor
+            .add_file("common.glsl", "#pragma once\nfloat x;")
+            .define("N", "4");
+        let processed = preprocessor
+            .process(
+                "main.frag",
+                "#version 330\n#include \"common.glsl\"\n\
+                 #include <common.glsl>\nvoid main() {}\n",
+            )
+            .unwrap();
+        assert_eq!(
+            processed.source,
+            "#version 330\n#define N 4\n#line 2 0\n#line 1 1\n\nfloat x;\n\
+             #line 3 0\n\nvoid main() {}\n"
+        );
+        let names: Vec<&str> =
+            processed.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["main.frag", "common.glsl"]);
+    }
+
+    #[test]
+    fn line_numbers_follow_the_glsl_version() {
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.add_file("a", "a;");
+        let source = |version: &str| {
+            let source = format!("{}\n#include \"a\"\nb;", version);
+            preprocessor.process("main", &source).unwrap().source
+        };
+        // before GLSL 3.30 `#line N` numbers the next line N + 1
+        assert_eq!(
+            source("#version 100"),
+            "#version 100\n#line 1 0\n#line 0 1\na;\n#line 2 0\nb;\n"
+        );
+        assert_eq!(
+            source("#version 120"),
+            "#version 120\n#line 1 0\n#line 0 1\na;\n#line 2 0\nb;\n"
+        );
+        assert_eq!(
+            source("#version 300 es"),
+            "#version 300 es\n#line 2 0\n#line 1 1\na;\n#line 3 0\nb;\n"
+        );
+        assert_eq!(
+            source("#version 330 core"),
+            "#version 330 core\n#line 2 0\n#line 1 1\na;\n#line 3 0\nb;\n"
+        );
+    }
+
+    #[test]
+    fn version_adds_the_header_when_missing() {
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.version(GlslVersion::Es100).define("N", "1");
+        assert_eq!(
+            preprocessor.process("main", "\nx;").unwrap().source,
+            "\n#version 100\nprecision mediump float;\n#define N 1\n\
+             #line 1 0\nx;\n"
+        );
+        preprocessor.version(GlslVersion::Es300);
+        assert_eq!(
+            preprocessor.process("main", "x;").unwrap().source,
+            format!(
+                "{}#define N 1\n#line 1 0\nx;\n",
+                GlslVersion::Es300.header()
+            )
+        );
+        // an explicit #version wins
+        assert_eq!(
+            preprocessor
+                .process("main", "#version 330\nx;")
+                .unwrap()
+                .source,
+            "#version 330\n#define N 1\n#line 2 0\nx;\n"
+        );
+    }
+
+    #[test]
+    fn comments_may_come_before_version() {
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.version(GlslVersion::Es300).define("N", "1");
+        let source = "// license\n/* more\n   license */\n\
+                      #version 330 core\nx;\n";
+        assert_eq!(
+            preprocessor.process("main", source).unwrap().source,
+            "// license\n/* more\n   license */\n#version 330 core\n\
+             #define N 1\n#line 5 0\nx;\n"
+        );
+        // without a #version the header goes after the comments
+        assert_eq!(
+            preprocessor
+                .process("main", "// license\nx;")
+                .unwrap()
+                .source,
+            format!(
+                "// license\n{}#define N 1\n#line 2 0\nx;\n",
+                GlslVersion::Es300.header()
+            )
+        );
+        // code after a closing comment ends the scan
+        assert_eq!(
+            preprocessor.process("main", "/* a */ x;").unwrap().source,
+            format!(
+                "{}#define N 1\n#line 1 0\n/* a */ x;\n",
+                GlslVersion::Es300.header()
+            )
+        );
+    }
+
+    #[test]
+    fn files_without_pragma_once_are_included_every_time() {
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.add_file("a.glsl", "#pragma optimize(once)\na;");
+        let processed = preprocessor
+            .process("main", "#include \"a.glsl\"\n#include \"a.glsl\"\n")
+            .unwrap();
+        assert_eq!(processed.source.matches("\na;\n").count(), 2);
+        assert_eq!(processed.files.len(), 2);
+    }
+
+    #[test]
+    fn pragma_once_is_matched_exactly() {
+        assert!(is_pragma_once("#pragma once"));
+        assert!(is_pragma_once("  #  pragma   once  "));
+        assert!(is_pragma_once("#pragma once // guard"));
+        assert!(!is_pragma_once("#pragma onceonly"));
+        assert!(!is_pragma_once("#pragma optimize(once)"));
+        assert!(!is_pragma_once("#pragma debug once"));
+        assert!(!is_pragma_once("// #pragma once"));
+    }
+
+    #[test]
+    fn recursive_includes_are_an_error() {
+        let mut preprocessor = Preprocessor::new();
+        preprocessor
+            .add_file("a", "x;\n#include \"b\"")
+            .add_file("b", "#include \"a\"");
+        assert_eq!(
+            preprocess_error(preprocessor.process_file("a")),
+            ("b".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn missing_includes_report_the_including_line() {
+        let preprocessor = Preprocessor::new();
+        assert_eq!(
+            preprocess_error(
+                preprocessor
+                    .process("main", "#version 100\nx;\n#include \"c\"")
+            ),
+            ("main".to_string(), 3)
+        );
+        assert_eq!(
+            preprocess_error(preprocessor.process_file("main")),
+            ("main".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn include_dirs_record_the_path() {
+        let dir = std::env::temp_dir()
+            .join(format!("red-preprocessor-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lib.glsl");
+        std::fs::write(&path, "float lib;\n").unwrap();
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.include_dir(&dir);
+        let processed = preprocessor
+            .process("main", "#include \"lib.glsl\"\n")
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(processed.files[0].path, None);
+        assert_eq!(processed.files[1].path, Some(path));
+        assert!(processed.source.contains("\nfloat lib;\n"));
+    }
+}
@@ -0,0 +1,1284 @@
+// Backend that talks to no driver and only logs what red asked for, so the
+// exact state emitted by Frame::draw can be asserted on in tests.
+use super::backend::{ActiveAttribute, Backend};
+use glow::ActiveUniform;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::os::raw::c_uint;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    CreateShader {
+        shader_type: u32,
+        shader: c_uint,
+    },
+    DeleteShader {
+        shader: c_uint,
+    },
+    ShaderSource {
+        shader: c_uint,
+        source: String,
+    },
+    CompileShader {
+        shader: c_uint,
+    },
+    GetShaderCompileStatus {
+        shader: c_uint,
+    },
+    GetShaderInfoLog {
+        shader: c_uint,
+    },
+    CreateProgram {
+        program: c_uint,
+    },
+    DeleteProgram {
+        program: c_uint,
+    },
+    AttachShader {
+        program: c_uint,
+        shader: c_uint,
+    },
+    DetachShader {
+        program: c_uint,
+        shader: c_uint,
+    },
+    BindAttribLocation {
+        program: c_uint,
+        index: u32,
+        name: String,
+    },
+    LinkProgram {
+        program: c_uint,
+    },
+    GetProgramLinkStatus {
+        program: c_uint,
+    },
+    GetProgramInfoLog {
+        program: c_uint,
+    },
+    GetActiveAttributes {
+        program: c_uint,
+    },
+    GetActiveAttribute {
+        program: c_uint,
+        index: u32,
+    },
+    GetActiveUniforms {
+        program: c_uint,
+    },
+    GetActiveUniform {
+        program: c_uint,
+        index: u32,
+    },
+    UseProgram {
+        program: Option<c_uint>,
+    },
+    GetUniformLocation {
+        program: c_uint,
+        name: String,
+    },
+    GetAttribLocation {
+        program: c_uint,
+        name: String,
+    },
+    CreateBuffer {
+        buffer: c_uint,
+    },
+    DeleteBuffer {
+        buffer: c_uint,
+    },
+    BindBuffer {
+        target: u32,
+        buffer: Option<c_uint>,
+    },
+    BufferDataU8Slice {
+        target: u32,
+        data: Vec<u8>,
+        usage: u32,
+    },
+    MapBufferRange {
+        target: u32,
+        offset: i32,
+        length: i32,
+        access: u32,
+    },
+    FlushMappedBufferRange {
+        target: u32,
+        offset: i32,
+        length: i32,
+    },
+    UnmapBuffer {
+        target: u32,
+    },
+    CreateVertexArray {
+        vertex_array: c_uint,
+    },
+    DeleteVertexArray {
+        vertex_array: c_uint,
+    },
+    BindVertexArray {
+        vertex_array: Option<c_uint>,
+    },
+    EnableVertexAttribArray {
+        index: u32,
+    },
+    VertexAttribPointerF32 {
+        index: u32,
+        size: i32,
+        data_type: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    },
+    VertexAttribPointerI32 {
+        index: u32,
+        size: i32,
+        data_type: u32,
+        stride: i32,
+        offset: i32,
+    },
+    VertexAttribDivisor {
+        index: u32,
+        divisor: u32,
+    },
+    CreateTexture {
+        texture: c_uint,
+    },
+    DeleteTexture {
+        texture: c_uint,
+    },
+    GenerateMipmap {
+        target: u32,
+    },
+    TexSubImage2D {
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<Vec<u8>>,
+    },
+    CreateFramebuffer {
+        framebuffer: c_uint,
+    },
+    DeleteFramebuffer {
+        framebuffer: c_uint,
+    },
+    BindFramebuffer {
+        target: u32,
+        framebuffer: Option<c_uint>,
+    },
+    FramebufferTexture2D {
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<c_uint>,
+        level: i32,
+    },
+    CheckFramebufferStatus {
+        target: u32,
+    },
+    ReadPixels {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+    },
+    CreateSampler {
+        sampler: c_uint,
+    },
+    DeleteSampler {
+        sampler: c_uint,
+    },
+    BindSampler {
+        unit: u32,
+        sampler: Option<c_uint>,
+    },
+    SamplerParameterI32 {
+        sampler: c_uint,
+        parameter: u32,
+        value: i32,
+    },
+    ActiveTexture {
+        unit: u32,
+    },
+    BindTexture {
+        target: u32,
+        texture: Option<c_uint>,
+    },
+    TexImage2D {
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<Vec<u8>>,
+    },
+    TexParameterI32 {
+        target: u32,
+        parameter: u32,
+        value: i32,
+    },
+    PixelStoreI32 {
+        parameter: u32,
+        value: i32,
+    },
+    Uniform1I32 {
+        location: Option<c_uint>,
+        x: i32,
+    },
+    Uniform1F32 {
+        location: Option<c_uint>,
+        x: f32,
+    },
+    Uniform2F32 {
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+    },
+    Uniform3F32 {
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+    Uniform4F32 {
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    },
+    UniformMatrix4F32Slice {
+        location: Option<c_uint>,
+        transpose: bool,
+        v: [f32; 16],
+    },
+    Enable {
+        parameter: u32,
+    },
+    Disable {
+        parameter: u32,
+    },
+    ColorMask {
+        red: bool,
+        green: bool,
+        blue: bool,
+        alpha: bool,
+    },
+    LineWidth {
+        width: f32,
+    },
+    PointSize {
+        size: f32,
+    },
+    BlendFunc {
+        src: u32,
+        dst: u32,
+    },
+    BlendFuncSeparate {
+        src_rgb: u32,
+        dst_rgb: u32,
+        src_alpha: u32,
+        dst_alpha: u32,
+    },
+    BlendEquationSeparate {
+        mode_rgb: u32,
+        mode_alpha: u32,
+    },
+    BlendColor {
+        red: f32,
+        green: f32,
+        blue: f32,
+        alpha: f32,
+    },
+    StencilFunc {
+        func: u32,
+        reference: i32,
+        mask: u32,
+    },
+    StencilFuncSeparate {
+        face: u32,
+        func: u32,
+        reference: i32,
+        mask: u32,
+    },
+    StencilMask {
+        mask: u32,
+    },
+    StencilMaskSeparate {
+        face: u32,
+        mask: u32,
+    },
+    StencilOp {
+        stencil_fail: u32,
+        depth_fail: u32,
+        pass: u32,
+    },
+    StencilOpSeparate {
+        face: u32,
+        stencil_fail: u32,
+        depth_fail: u32,
+        pass: u32,
+    },
+    DepthFunc {
+        func: u32,
+    },
+    DepthMask {
+        value: bool,
+    },
+    DepthRangeF32 {
+        near: f32,
+        far: f32,
+    },
+    PolygonOffset {
+        factor: f32,
+        units: f32,
+    },
+    Viewport {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    ClearColor {
+        red: f32,
+        green: f32,
+        blue: f32,
+        alpha: f32,
+    },
+    ClearStencil {
+        stencil: i32,
+    },
+    ClearDepthF32 {
+        depth: f32,
+    },
+    Clear {
+        mask: u32,
+    },
+    DrawElements {
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+    },
+    DrawElementsInstanced {
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+        instance_count: i32,
+    },
+    DrawArrays {
+        mode: u32,
+        first: i32,
+        count: i32,
+    },
+    DrawArraysInstanced {
+        mode: u32,
+        first: i32,
+        count: i32,
+        instance_count: i32,
+    },
+    GetError,
+    GetParameterI32 {
+        parameter: u32,
+    },
+    GetParameterString {
+        parameter: u32,
+    },
+}
+
+#[derive(Debug, Default)]
+struct State {
+    events: Vec<Event>,
+    last_name: c_uint,
+    locations: HashMap<(c_uint, String), c_uint>,
+    mapped: Vec<u8>,
+    // reported by every program, see Recorder::set_active_attributes
+    active_attributes: Vec<ActiveAttribute>,
+    // (size, type, name), glow::ActiveUniform is not Debug
+    active_uniforms: Vec<(i32, u32, String)>,
+    no_reflection: bool,
+    // (success, log) for the next compiles and links, then per object
+    compile_script: VecDeque<(bool, String)>,
+    link_script: VecDeque<(bool, String)>,
+    compiled: HashMap<c_uint, (bool, String)>,
+    linked: HashMap<c_uint, (bool, String)>,
+    parameters: HashMap<u32, i32>,
+}
+
+// Cloned recorders share one log: keep a clone around, hand the other one
+// to `GL::from_backend`.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    state: Rc<RefCell<State>>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    pub fn events(&self) -> Vec<Event> {
+        self.state.borrow().events.clone()
+    }
+
+    // Returns recorded events and starts a fresh log
+    pub fn take_events(&self) -> Vec<Event> {
+        std::mem::take(&mut self.state.borrow_mut().events)
+    }
+
+    pub fn clear(&self) {
+        self.state.borrow_mut().events.clear();
+    }
+
+    // Active attributes every program reports after linking, none by
+    // default
+    pub fn set_active_attributes(&self, attributes: Vec<ActiveAttribute>) {
+        self.state.borrow_mut().active_attributes = attributes;
+    }
+
+    // Same for active uniforms
+    pub fn set_active_uniforms(&self, uniforms: Vec<ActiveUniform>) {
+        self.state.borrow_mut().active_uniforms = uniforms
+            .into_iter()
+            .map(|uniform| (uniform.size, uniform.utype, uniform.name))
+            .collect();
+    }
+
+    // Queues the status and info log of a coming compile; compiles with
+    // nothing queued succeed with an empty log
+    pub fn script_compile(&self, success: bool, log: &str) {
+        self.state
+            .borrow_mut()
+            .compile_script
+            .push_back((success, log.to_string()));
+    }
+
+    // Same as script_compile for program links
+    pub fn script_link(&self, success: bool, log: &str) {
+        self.state
+            .borrow_mut()
+            .link_script
+            .push_back((success, log.to_string()));
+    }
+
+    // What get_parameter_i32 reports for `parameter`. Unset parameters
+    // report 0, the pixel store alignments their initial 4 and the texture
+    // unit count the GL 3.3 minimum of 48. GL reads the unit count once
+    // when it is created.
+    pub fn set_parameter(&self, parameter: u32, value: i32) {
+        self.state.borrow_mut().parameters.insert(parameter, value);
+    }
+
+    // Act like a backend that can't list attributes
+    pub fn disable_reflection(&self) {
+        self.state.borrow_mut().no_reflection = true;
+    }
+
+    fn record(&self, event: Event) {
+        self.state.borrow_mut().events.push(event);
+    }
+
+    fn next_name(&self) -> c_uint {
+        let mut state = self.state.borrow_mut();
+        state.last_name += 1;
+        state.last_name
+    }
+
+    // stable per program and name, like a driver would assign them
+    fn location(&self, program: c_uint, name: &str) -> c_uint {
+        let mut state = self.state.borrow_mut();
+        let next = state.locations.len() as c_uint;
+        *state
+            .locations
+            .entry((program, name.to_string()))
+            .or_insert(next)
+    }
+}
+
+impl Backend for Recorder {
+    unsafe fn create_shader(&self, shader_type: u32) -> Result<c_uint, String> {
+        let shader = self.next_name();
+        self.record(Event::CreateShader {
+            shader_type,
+            shader,
+        });
+        Ok(shader)
+    }
+
+    unsafe fn delete_shader(&self, shader: c_uint) {
+        self.record(Event::DeleteShader { shader });
+    }
+
+    unsafe fn shader_source(&self, shader: c_uint, source: &str) {
+        self.record(Event::ShaderSource {
+            shader,
+            source: source.to_string(),
+        });
+    }
+
+    unsafe fn compile_shader(&self, shader: c_uint) {
+        self.record(Event::CompileShader { shader });
+        let mut state = self.state.borrow_mut();
+        if let Some(result) = state.compile_script.pop_front() {
+            state.compiled.insert(shader, result);
+        }
+    }
+
+    unsafe fn get_shader_compile_status(&self, shader: c_uint) -> bool {
+        self.record(Event::GetShaderCompileStatus { shader });
+        self.state
+            .borrow()
+            .compiled
+            .get(&shader)
+            .is_none_or(|(success, _)| *success)
+    }
+
+    unsafe fn get_shader_info_log(&self, shader: c_uint) -> String {
+        self.record(Event::GetShaderInfoLog { shader });
+        self.state
+            .borrow()
+            .compiled
+            .get(&shader)
+            .map(|(_, log)| log.clone())
+            .unwrap_or_default()
+    }
+
+    unsafe fn create_program(&self) -> Result<c_uint, String> {
+        let program = self.next_name();
+        self.record(Event::CreateProgram { program });
+        Ok(program)
+    }
+
+    unsafe fn delete_program(&self, program: c_uint) {
+        self.record(Event::DeleteProgram { program });
+    }
+
+    unsafe fn attach_shader(&self, program: c_uint, shader: c_uint) {
+        self.record(Event::AttachShader { program, shader });
+    }
+
+    unsafe fn detach_shader(&self, program: c_uint, shader: c_uint) {
+        self.record(Event::DetachShader { program, shader });
+    }
+
+    // later get_attrib_location calls report the bound index
+    unsafe fn bind_attrib_location(
+        &self,
+        program: c_uint,
+        index: u32,
+        name: &str,
+    ) {
+        self.record(Event::BindAttribLocation {
+            program,
+            index,
+            name: name.to_string(),
+        });
+        self.state
+            .borrow_mut()
+            .locations
+            .insert((program, name.to_string()), index);
+    }
+
+    unsafe fn link_program(&self, program: c_uint) {
+        self.record(Event::LinkProgram { program });
+        let mut state = self.state.borrow_mut();
+        if let Some(result) = state.link_script.pop_front() {
+            state.linked.insert(program, result);
+        }
+    }
+
+    unsafe fn get_program_link_status(&self, program: c_uint) -> bool {
+        self.record(Event::GetProgramLinkStatus { program });
+        self.state
+            .borrow()
+            .linked
+            .get(&program)
+            .is_none_or(|(success, _)| *success)
+    }
+
+    unsafe fn get_program_info_log(&self, program: c_uint) -> String {
+        self.record(Event::GetProgramInfoLog { program });
+        self.state
+            .borrow()
+            .linked
+            .get(&program)
+            .map(|(_, log)| log.clone())
+            .unwrap_or_default()
+    }
+
+    unsafe fn get_active_attributes(&self, program: c_uint) -> Option<u32> {
+        self.record(Event::GetActiveAttributes { program });
+        let state = self.state.borrow();
+        if state.no_reflection {
+            return None;
+        }
+        Some(state.active_attributes.len() as u32)
+    }
+
+    unsafe fn get_active_attribute(
+        &self,
+        program: c_uint,
+        index: u32,
+    ) -> Option<ActiveAttribute> {
+        self.record(Event::GetActiveAttribute { program, index });
+        self.state
+            .borrow()
+            .active_attributes
+            .get(index as usize)
+            .cloned()
+    }
+
+    unsafe fn get_active_uniforms(&self, program: c_uint) -> u32 {
+        self.record(Event::GetActiveUniforms { program });
+        self.state.borrow().active_uniforms.len() as u32
+    }
+
+    unsafe fn get_active_uniform(
+        &self,
+        program: c_uint,
+        index: u32,
+    ) -> Option<ActiveUniform> {
+        self.record(Event::GetActiveUniform { program, index });
+        let state = self.state.borrow();
+        let (size, utype, name) = state.active_uniforms.get(index as usize)?;
+        Some(ActiveUniform {
+            size: *size,
+            utype: *utype,
+            name: name.clone(),
+        })
+    }
+
+    unsafe fn use_program(&self, program: Option<c_uint>) {
+        self.record(Event::UseProgram { program });
+    }
+
+    unsafe fn get_uniform_location(
+        &self,
+        program: c_uint,
+        name: &str,
+    ) -> Option<c_uint> {
+        self.record(Event::GetUniformLocation {
+            program,
+            name: name.to_string(),
+        });
+        Some(self.location(program, name))
+    }
+
+    unsafe fn get_attrib_location(&self, program: c_uint, name: &str) -> i32 {
+        self.record(Event::GetAttribLocation {
+            program,
+            name: name.to_string(),
+        });
+        self.location(program, name) as i32
+    }
+
+    unsafe fn create_buffer(&self) -> Result<c_uint, String> {
+        let buffer = self.next_name();
+        self.record(Event::CreateBuffer { buffer });
+        Ok(buffer)
+    }
+
+    unsafe fn delete_buffer(&self, buffer: c_uint) {
+        self.record(Event::DeleteBuffer { buffer });
+    }
+
+    unsafe fn bind_buffer(&self, target: u32, buffer: Option<c_uint>) {
+        self.record(Event::BindBuffer { target, buffer });
+    }
+
+    unsafe fn buffer_data_u8_slice(
+        &self,
+        target: u32,
+        data: &[u8],
+        usage: u32,
+    ) {
+        self.record(Event::BufferDataU8Slice {
+            target,
+            data: data.to_vec(),
+            usage,
+        });
+    }
+
+    unsafe fn map_buffer_range(
+        &self,
+        target: u32,
+        offset: i32,
+        length: i32,
+        access: u32,
+    ) -> *mut u8 {
+        self.record(Event::MapBufferRange {
+            target,
+            offset,
+            length,
+            access,
+        });
+        // stays valid until the next map, MapArray never holds two at once
+        let mut state = self.state.borrow_mut();
+        state.mapped = vec![0; length as usize];
+        state.mapped.as_mut_ptr()
+    }
+
+    unsafe fn flush_mapped_buffer_range(
+        &self,
+        target: u32,
+        offset: i32,
+        length: i32,
+    ) {
+        self.record(Event::FlushMappedBufferRange {
+            target,
+            offset,
+            length,
+        });
+    }
+
+    unsafe fn unmap_buffer(&self, target: u32) {
+        self.record(Event::UnmapBuffer { target });
+    }
+
+    unsafe fn create_vertex_array(&self) -> Result<c_uint, String> {
+        let vertex_array = self.next_name();
+        self.record(Event::CreateVertexArray { vertex_array });
+        Ok(vertex_array)
+    }
+
+    unsafe fn delete_vertex_array(&self, vertex_array: c_uint) {
+        self.record(Event::DeleteVertexArray { vertex_array });
+    }
+
+    unsafe fn bind_vertex_array(&self, vertex_array: Option<c_uint>) {
+        self.record(Event::BindVertexArray { vertex_array });
+    }
+
+    unsafe fn enable_vertex_attrib_array(&self, index: u32) {
+        self.record(Event::EnableVertexAttribArray { index });
+    }
+
+    unsafe fn vertex_attrib_pointer_f32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    ) {
+        self.record(Event::VertexAttribPointerF32 {
+            index,
+            size,
+            data_type,
+            normalized,
+            stride,
+            offset,
+        });
+    }
+
+    unsafe fn vertex_attrib_pointer_i32(
+        &self,
+        index: u32,
+        size: i32,
+        data_type: u32,
+        stride: i32,
+        offset: i32,
+    ) {
+        self.record(Event::VertexAttribPointerI32 {
+            index,
+            size,
+            data_type,
+            stride,
+            offset,
+        });
+    }
+
+    unsafe fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.record(Event::VertexAttribDivisor { index, divisor });
+    }
+
+    unsafe fn create_texture(&self) -> Result<c_uint, String> {
+        let texture = self.next_name();
+        self.record(Event::CreateTexture { texture });
+        Ok(texture)
+    }
+
+    unsafe fn delete_texture(&self, texture: c_uint) {
+        self.record(Event::DeleteTexture { texture });
+    }
+
+    unsafe fn generate_mipmap(&self, target: u32) {
+        self.record(Event::GenerateMipmap { target });
+    }
+
+    unsafe fn tex_sub_image_2d_u8_slice(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<&[u8]>,
+    ) {
+        self.record(Event::TexSubImage2D {
+            target,
+            level,
+            x_offset,
+            y_offset,
+            width,
+            height,
+            format,
+            ty,
+            pixels: pixels.map(|p| p.to_vec()),
+        });
+    }
+
+    unsafe fn create_framebuffer(&self) -> Result<c_uint, String> {
+        let framebuffer = self.next_name();
+        self.record(Event::CreateFramebuffer { framebuffer });
+        Ok(framebuffer)
+    }
+
+    unsafe fn delete_framebuffer(&self, framebuffer: c_uint) {
+        self.record(Event::DeleteFramebuffer { framebuffer });
+    }
+
+    unsafe fn bind_framebuffer(
+        &self,
+        target: u32,
+        framebuffer: Option<c_uint>,
+    ) {
+        self.record(Event::BindFramebuffer {
+            target,
+            framebuffer,
+        });
+    }
+
+    unsafe fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<c_uint>,
+        level: i32,
+    ) {
+        self.record(Event::FramebufferTexture2D {
+            target,
+            attachment,
+            texture_target,
+            texture,
+            level,
+        });
+    }
+
+    unsafe fn check_framebuffer_status(&self, target: u32) -> u32 {
+        self.record(Event::CheckFramebufferStatus { target });
+        glow::FRAMEBUFFER_COMPLETE
+    }
+
+    // leaves `pixels` as they are
+    unsafe fn read_pixels(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        _pixels: &mut [u8],
+    ) -> Result<(), String> {
+        self.record(Event::ReadPixels {
+            x,
+            y,
+            width,
+            height,
+            format,
+            ty,
+        });
+        Ok(())
+    }
+
+    unsafe fn create_sampler(&self) -> Result<c_uint, String> {
+        let sampler = self.next_name();
+        self.record(Event::CreateSampler { sampler });
+        Ok(sampler)
+    }
+
+    unsafe fn delete_sampler(&self, sampler: c_uint) {
+        self.record(Event::DeleteSampler { sampler });
+    }
+
+    unsafe fn bind_sampler(&self, unit: u32, sampler: Option<c_uint>) {
+        self.record(Event::BindSampler { unit, sampler });
+    }
+
+    unsafe fn sampler_parameter_i32(
+        &self,
+        sampler: c_uint,
+        parameter: u32,
+        value: i32,
+    ) {
+        self.record(Event::SamplerParameterI32 {
+            sampler,
+            parameter,
+            value,
+        });
+    }
+
+    unsafe fn active_texture(&self, unit: u32) {
+        self.record(Event::ActiveTexture { unit });
+    }
+
+    unsafe fn bind_texture(&self, target: u32, texture: Option<c_uint>) {
+        self.record(Event::BindTexture { target, texture });
+    }
+
+    unsafe fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: u32,
+        ty: u32,
+        pixels: Option<&[u8]>,
+    ) {
+        self.record(Event::TexImage2D {
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            border,
+            format,
+            ty,
+            pixels: pixels.map(|p| p.to_vec()),
+        });
+    }
+
+    unsafe fn tex_parameter_i32(
+        &self,
+        target: u32,
+        parameter: u32,
+        value: i32,
+    ) {
+        self.record(Event::TexParameterI32 {
+            target,
+            parameter,
+            value,
+        });
+    }
+
+    unsafe fn pixel_store_i32(&self, parameter: u32, value: i32) {
+        self.record(Event::PixelStoreI32 { parameter, value });
+    }
+
+    unsafe fn uniform_1_i32(&self, location: Option<c_uint>, x: i32) {
+        self.record(Event::Uniform1I32 { location, x });
+    }
+
+    unsafe fn uniform_1_f32(&self, location: Option<c_uint>, x: f32) {
+        self.record(Event::Uniform1F32 { location, x });
+    }
+
+    unsafe fn uniform_2_f32(&self, location: Option<c_uint>, x: f32, y: f32) {
+        self.record(Event::Uniform2F32 { location, x, y });
+    }
+
+    unsafe fn uniform_3_f32(
+        &self,
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+    ) {
+        self.record(Event::Uniform3F32 { location, x, y, z });
+    }
+
+    unsafe fn uniform_4_f32(
+        &self,
+        location: Option<c_uint>,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    ) {
+        self.record(Event::Uniform4F32 {
+            location,
+            x,
+            y,
+            z,
+            w,
+        });
+    }
+
+    unsafe fn uniform_matrix_4_f32_slice(
+        &self,
+        location: Option<c_uint>,
+        transpose: bool,
+        v: &[f32; 16],
+    ) {
+        self.record(Event::UniformMatrix4F32Slice {
+            location,
+            transpose,
+            v: *v,
+        });
+    }
+
+    unsafe fn enable(&self, parameter: u32) {
+        self.record(Event::Enable { parameter });
+    }
+
+    unsafe fn disable(&self, parameter: u32) {
+        self.record(Event::Disable { parameter });
+    }
+
+    unsafe fn color_mask(
+        &self,
+        red: bool,
+        green: bool,
+        blue: bool,
+        alpha: bool,
+    ) {
+        self.record(Event::ColorMask {
+            red,
+            green,
+            blue,
+            alpha,
+        });
+    }
+
+    unsafe fn line_width(&self, width: f32) {
+        self.record(Event::LineWidth { width });
+    }
+
+    unsafe fn point_size(&self, size: f32) {
+        self.record(Event::PointSize { size });
+    }
+
+    unsafe fn blend_func(&self, src: u32, dst: u32) {
+        self.record(Event::BlendFunc { src, dst });
+    }
+
+    unsafe fn blend_func_separate(
+        &self,
+        src_rgb: u32,
+        dst_rgb: u32,
+        src_alpha: u32,
+        dst_alpha: u32,
+    ) {
+        self.record(Event::BlendFuncSeparate {
+            src_rgb,
+            dst_rgb,
+            src_alpha,
+            dst_alpha,
+        });
+    }
+
+    unsafe fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        self.record(Event::BlendEquationSeparate {
+            mode_rgb,
+            mode_alpha,
+        });
+    }
+
+    unsafe fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.record(Event::BlendColor {
+            red,
+            green,
+            blue,
+            alpha,
+        });
+    }
+
+    unsafe fn stencil_func(&self, func: u32, reference: i32, mask: u32) {
+        self.record(Event::StencilFunc {
+            func,
+            reference,
+            mask,
+        });
+    }
+
+    unsafe fn stencil_func_separate(
+        &self,
+        face: u32,
+        func: u32,
+        reference: i32,
+        mask: u32,
+    ) {
+        self.record(Event::StencilFuncSeparate {
+            face,
+            func,
+            reference,
+            mask,
+        });
+    }
+
+    unsafe fn stencil_mask(&self, mask: u32) {
+        self.record(Event::StencilMask { mask });
+    }
+
+    unsafe fn stencil_mask_separate(&self, face: u32, mask: u32) {
+        self.record(Event::StencilMaskSeparate { face, mask });
+    }
+
+    unsafe fn stencil_op(&self, stencil_fail: u32, depth_fail: u32, pass: u32) {
+        self.record(Event::StencilOp {
+            stencil_fail,
+            depth_fail,
+            pass,
+        });
+    }
+
+    unsafe fn stencil_op_separate(
+        &self,
+        face: u32,
+        stencil_fail: u32,
+        depth_fail: u32,
+        pass: u32,
+    ) {
+        self.record(Event::StencilOpSeparate {
+            face,
+            stencil_fail,
+            depth_fail,
+            pass,
+        });
+    }
+
+    unsafe fn depth_func(&self, func: u32) {
+        self.record(Event::DepthFunc { func });
+    }
+
+    unsafe fn depth_mask(&self, value: bool) {
+        self.record(Event::DepthMask { value });
+    }
+
+    unsafe fn depth_range_f32(&self, near: f32, far: f32) {
+        self.record(Event::DepthRangeF32 { near, far });
+    }
+
+    unsafe fn polygon_offset(&self, factor: f32, units: f32) {
+        self.record(Event::PolygonOffset { factor, units });
+    }
+
+    unsafe fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.record(Event::Viewport {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    unsafe fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.record(Event::ClearColor {
+            red,
+            green,
+            blue,
+            alpha,
+        });
+    }
+
+    unsafe fn clear_stencil(&self, stencil: i32) {
+        self.record(Event::ClearStencil { stencil });
+    }
+
+    unsafe fn clear_depth_f32(&self, depth: f32) {
+        self.record(Event::ClearDepthF32 { depth });
+    }
+
+    unsafe fn clear(&self, mask: u32) {
+        self.record(Event::Clear { mask });
+    }
+
+    unsafe fn draw_elements(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+    ) {
+        self.record(Event::DrawElements {
+            mode,
+            count,
+            element_type,
+            offset,
+        });
+    }
+
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: u32,
+        count: i32,
+        element_type: u32,
+        offset: i32,
+        instance_count: i32,
+    ) {
+        self.record(Event::DrawElementsInstanced {
+            mode,
+            count,
+            element_type,
+            offset,
+            instance_count,
+        });
+    }
+
+    unsafe fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        self.record(Event::DrawArrays { mode, first, count });
+    }
+
+    unsafe fn draw_arrays_instanced(
+        &self,
+        mode: u32,
+        first: i32,
+        count: i32,
+        instance_count: i32,
+    ) {
+        self.record(Event::DrawArraysInstanced {
+            mode,
+            first,
+            count,
+            instance_count,
+        });
+    }
+
+    unsafe fn get_error(&self) -> u32 {
+        self.record(Event::GetError);
+        glow::NO_ERROR
+    }
+
+    unsafe fn get_parameter_i32(&self, parameter: u32) -> i32 {
+        self.record(Event::GetParameterI32 { parameter });
+        let state = self.state.borrow();
+        match state.parameters.get(&parameter) {
+            Some(value) => *value,
+            None if parameter == glow::PACK_ALIGNMENT => 4,
+            None if parameter == glow::UNPACK_ALIGNMENT => 4,
+            None if parameter == glow::MAX_COMBINED_TEXTURE_IMAGE_UNITS => 48,
+            None => 0,
+        }
+    }
+
+    // reports a desktop 3.3 context
+    unsafe fn get_parameter_string(&self, parameter: u32) -> String {
+        self.record(Event::GetParameterString { parameter });
+        match parameter {
+            glow::VERSION => "3.3.0 red recorder".to_string(),
+            glow::SHADING_LANGUAGE_VERSION => "3.30".to_string(),
+            _ => String::new(),
+        }
+    }
+}
@@ -0,0 +1,417 @@
+// Active attributes and uniforms of a linked program, queried once after
+// linking. See Program::attributes and Program::uniforms.
+use super::data::VertexAttribute;
+use super::GL;
+use std::fmt;
+use std::os::raw::c_uint;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BaseType {
+    Float,
+    Double,
+    Int,
+    UnsignedInt,
+    Bool,
+    Sampler,
+    Other,
+}
+
+// GLSL type of an active variable, the GL enum the driver reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlslType(pub u32);
+
+impl GlslType {
+    // (base type, number of components, GLSL name)
+    fn info(self) -> (BaseType, usize, &'static str) {
+        match self.0 {
+            glow::FLOAT => (BaseType::Float, 1, "float"),
+            glow::FLOAT_VEC2 => (BaseType::Float, 2, "vec2"),
+            glow::FLOAT_VEC3 => (BaseType::Float, 3, "vec3"),
+            glow::FLOAT_VEC4 => (BaseType::Float, 4, "vec4"),
+            glow::FLOAT_MAT2 => (BaseType::Float, 4, "mat2"),
+            glow::FLOAT_MAT3 => (BaseType::Float, 9, "mat3"),
+            glow::FLOAT_MAT4 => (BaseType::Float, 16, "mat4"),
+            glow::FLOAT_MAT2x3 => (BaseType::Float, 6, "mat2x3"),
+            glow::FLOAT_MAT2x4 => (BaseType::Float, 8, "mat2x4"),
+            glow::FLOAT_MAT3x2 => (BaseType::Float, 6, "mat3x2"),
+            glow::FLOAT_MAT3x4 => (BaseType::Float, 12, "mat3x4"),
+            glow::FLOAT_MAT4x2 => (BaseType::Float, 8, "mat4x2"),
+            glow::FLOAT_MAT4x3 => (BaseType::Float, 12, "mat4x3"),
+            glow::DOUBLE => (BaseType::Double, 1, "double"),
+            glow::DOUBLE_VEC2 => (BaseType::Double, 2, "dvec2"),
+            glow::DOUBLE_VEC3 => (BaseType::Double, 3, "dvec3"),
+            glow::DOUBLE_VEC4 => (BaseType::Double, 4, "dvec4"),
+            glow::INT => (BaseType::Int, 1, "int"),
+            glow::INT_VEC2 => (BaseType::Int, 2, "ivec2"),
+            glow::INT_VEC3 => (BaseType::Int, 3, "ivec3"),
+            glow::INT_VEC4 => (BaseType::Int, 4, "ivec4"),
+            glow::UNSIGNED_INT => (BaseType::UnsignedInt, 1, "uint"),
+            glow::UNSIGNED_INT_VEC2 => (BaseType::UnsignedInt, 2, "uvec2"),
+            glow::UNSIGNED_INT_VEC3 => (BaseType::UnsignedInt, 3, "uvec3"),
+            glow::UNSIGNED_INT_VEC4 => (BaseType::UnsignedInt, 4, "uvec4"),
+            glow::BOOL => (BaseType::Bool, 1, "bool"),
+            glow::BOOL_VEC2 => (BaseType::Bool, 2, "bvec2"),
+            glow::BOOL_VEC3 => (BaseType::Bool, 3, "bvec3"),
+            glow::BOOL_VEC4 => (BaseType::Bool, 4, "bvec4"),
+            glow::SAMPLER_2D => (BaseType::Sampler, 1, "sampler2D"),
+            glow::SAMPLER_3D => (BaseType::Sampler, 1, "sampler3D"),
+            glow::SAMPLER_CUBE => (BaseType::Sampler, 1, "samplerCube"),
+            glow::SAMPLER_2D_SHADOW => {
+                (BaseType::Sampler, 1, "sampler2DShadow")
+            }
+            glow::SAMPLER_2D_ARRAY => (BaseType::Sampler, 1, "sampler2DArray"),
+            glow::SAMPLER_2D_ARRAY_SHADOW => {
+                (BaseType::Sampler, 1, "sampler2DArrayShadow")
+            }
+            glow::SAMPLER_CUBE_SHADOW => {
+                (BaseType::Sampler, 1, "samplerCubeShadow")
+            }
+            glow::INT_SAMPLER_2D => (BaseType::Sampler, 1, "isampler2D"),
+            glow::UNSIGNED_INT_SAMPLER_2D => {
+                (BaseType::Sampler, 1, "usampler2D")
+            }
+            _ => (BaseType::Other, 1, "?"),
+        }
+    }
+
+    pub fn base_type(self) -> BaseType {
+        self.info().0
+    }
+
+    // scalars per element, e.g. 3 for vec3 and 16 for mat4
+    pub fn components(self) -> usize {
+        self.info().1
+    }
+}
+
+impl fmt::Display for GlslType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.info() {
+            (BaseType::Other, _, _) => write!(f, "{:#x}", self.0),
+            (_, _, name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeInfo {
+    pub name: String,
+    pub location: c_uint,
+    pub kind: GlslType,
+    // array length, 1 for non-arrays
+    pub size: i32,
+}
+
+// Arrays are listed once under their name without "[0]"
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformInfo {
+    pub name: String,
+    // None for uniforms in a uniform block
+    pub location: Option<c_uint>,
+    pub kind: GlslType,
+    pub size: i32,
+}
+
+// Built-ins like gl_VertexID have no location and are left out. None when
+// the backend can't list attributes.
+pub(crate) unsafe fn active_attributes(
+    gl: &GL,
+    program: c_uint,
+) -> Option<Vec<AttributeInfo>> {
+    let mut attributes: Vec<AttributeInfo> = (0..gl
+        .get_active_attributes(program)?)
+        .filter_map(|index| gl.get_active_attribute(program, index))
+        .filter_map(|attribute| {
+            let location = gl.get_attrib_location(program, &attribute.name);
+            if location < 0 {
+                return None;
+            }
+            Some(AttributeInfo {
+                location: location as c_uint,
+                kind: GlslType(attribute.atype),
+                size: attribute.size,
+                name: attribute.name,
+            })
+        })
+        .collect();
+    attributes.sort_by_key(|attribute| attribute.location);
+    Some(attributes)
+}
+
+pub(crate) unsafe fn active_uniforms(
+    gl: &GL,
+    program: c_uint,
+) -> Vec<UniformInfo> {
+    (0..gl.get_active_uniforms(program))
+        .filter_map(|index| gl.get_active_uniform(program, index))
+        .map(|uniform| UniformInfo {
+            location: gl.get_uniform_location(program, &uniform.name),
+            name: uniform
+                .name
+                .strip_suffix("[0]")
+                .unwrap_or(&uniform.name)
+                .to_string(),
+            kind: GlslType(uniform.utype),
+            size: uniform.size,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    // the field is not an active attribute, e.g. optimized out
+    Inactive {
+        field: &'static str,
+    },
+    Components {
+        field: &'static str,
+        // what the program declares
+        expected: usize,
+        found: usize,
+    },
+    BaseType {
+        field: &'static str,
+        expected: BaseType,
+        found: BaseType,
+    },
+    // the field has a fixed location the program didn't bind it to
+    Location {
+        field: &'static str,
+        expected: c_uint,
+        found: c_uint,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Inactive { field } => {
+                write!(f, "field {} is not an active attribute", field)
+            }
+            Mismatch::Components {
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "field {} has {} components, the attribute has {}",
+                field, found, expected
+            ),
+            Mismatch::BaseType {
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "field {} is {:?}, the attribute is {:?}",
+                field, found, expected
+            ),
+            Mismatch::Location {
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "field {} has location {}, the attribute has {}",
+                field, found, expected
+            ),
+        }
+    }
+}
+
+// Result of Program::validate_vertex
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VertexReport {
+    pub mismatches: Vec<Mismatch>,
+    // active attributes no field of the vertex type feeds
+    pub unused: Vec<AttributeInfo>,
+}
+
+impl VertexReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty() && self.unused.is_empty()
+    }
+}
+
+impl fmt::Display for VertexReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "vertex layout matches the program");
+        }
+        let mut lines = self
+            .mismatches
+            .iter()
+            .map(|mismatch| mismatch.to_string())
+            .chain(self.unused.iter().map(|attribute| {
+                format!(
+                    "attribute {} {} at location {} is not fed by any field",
+                    attribute.kind, attribute.name, attribute.location
+                )
+            }));
+        if let Some(line) = lines.next() {
+            write!(f, "{}", line)?;
+        }
+        for line in lines {
+            write!(f, "\n{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn validate_vertex(
+    attributes: &[AttributeInfo],
+    fields: &[VertexAttribute],
+) -> VertexReport {
+    let mut report = VertexReport::default();
+    for field in fields {
+        let attribute = match attributes.iter().find(|a| a.name == field.name) {
+            Some(attribute) => attribute,
+            None => {
+                report
+                    .mismatches
+                    .push(Mismatch::Inactive { field: field.name });
+                continue;
+            }
+        };
+        let expected = attribute.kind.components();
+        if expected != field.components {
+            report.mismatches.push(Mismatch::Components {
+                field: field.name,
+                expected,
+                found: field.components,
+            });
+        }
+        match field.location {
+            Some(found) if found != attribute.location => {
+                report.mismatches.push(Mismatch::Location {
+                    field: field.name,
+                    expected: attribute.location,
+                    found,
+                })
+            }
+            _ => (),
+        }
+        let expected = attribute.kind.base_type();
+        if expected != field.base_type {
+            report.mismatches.push(Mismatch::BaseType {
+                field: field.name,
+                expected,
+                found: field.base_type,
+            });
+        }
+    }
+    report.unused = attributes
+        .iter()
+        .filter(|a| fields.iter().all(|field| field.name != a.name))
+        .cloned()
+        .collect();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::ActiveAttribute;
+    use crate::data::{f32_f32, f32_f32_f32_f32};
+    use crate::recorder::{Event, Recorder};
+    use crate::{Program, Shader, VertexAttribPointers};
+
+    #[derive(VertexAttribPointers, Copy, Clone)]
+    #[repr(C, packed)]
+    struct Vertex {
+        #[location = "0"]
+        position: f32_f32,
+        color: f32_f32_f32_f32,
+    }
+
+    fn attribute(name: &str, atype: u32) -> ActiveAttribute {
+        ActiveAttribute {
+            size: 1,
+            atype,
+            name: name.to_string(),
+        }
+    }
+
+    fn program(recorder: &Recorder) -> Program {
+        let gl = GL::from_backend(recorder.clone());
+        let shader = Shader::from_vert_source(&gl, "").unwrap();
+        Program::from_shaders_with_layout::<Vertex>(&gl, &[shader]).unwrap()
+    }
+
+    #[test]
+    fn matching_layout_validates_clean() {
+        let recorder = Recorder::new();
+        recorder.set_active_attributes(vec![
+            attribute("position", glow::FLOAT_VEC2),
+            attribute("color", glow::FLOAT_VEC4),
+        ]);
+        let report = program(&recorder).validate_vertex::<Vertex>().unwrap();
+        assert_eq!(report, VertexReport::default());
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn mismatches_are_reported() {
+        let recorder = Recorder::new();
+        recorder.set_active_attributes(vec![
+            attribute("position", glow::FLOAT_VEC2),
+            attribute("color", glow::FLOAT_VEC3),
+            attribute("normal", glow::FLOAT_VEC3),
+        ]);
+        let report = program(&recorder).validate_vertex::<Vertex>().unwrap();
+        assert_eq!(
+            report.mismatches,
+            vec![Mismatch::Components {
+                field: "color",
+                expected: 3,
+                found: 4,
+            }]
+        );
+        assert_eq!(report.unused.len(), 1);
+        assert_eq!(report.unused[0].name, "normal");
+    }
+
+    #[test]
+    fn fixed_locations_are_bound_before_linking() {
+        let recorder = Recorder::new();
+        let program = program(&recorder);
+        assert!(recorder.events().contains(&Event::BindAttribLocation {
+            program: program.id(),
+            index: 0,
+            name: "position".to_string(),
+        }));
+    }
+
+    #[test]
+    fn fixed_location_mismatch_is_an_error() {
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        let shader = Shader::from_vert_source(&gl, "").unwrap();
+        let program = Program::from_shaders(&gl, &[shader]).unwrap();
+        // the driver put "color" at the location "position" fixes
+        unsafe {
+            gl.get_attrib_location(program.id(), "color");
+        }
+        let buffer = VertexBuffer::new(&gl, &[]).unwrap();
+        assert_eq!(
+            program.set_layout(&gl, &buffer.vao, &[&buffer]),
+            Err(crate::Error::AttributeLocation {
+                name: "position".to_string(),
+                found: 1,
+                fixed: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn missing_reflection_is_an_error() {
+        let recorder = Recorder::new();
+        recorder.disable_reflection();
+        let program = program(&recorder);
+        assert!(program.attributes().is_err());
+        assert!(matches!(
+            program.validate_vertex::<Vertex>(),
+            Err(crate::Error::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+// Programs built from shader files, rebuilt when the files change on disk.
+use super::data::VertexLayout;
+use super::preprocessor::Preprocessor;
+use super::shader::{fixed_locations, Program, Shader};
+use super::{Error, GL};
+use std::ops::Deref;
+use std::os::raw::c_uint;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Default)]
+pub struct ProgramBuilder {
+    shaders: Vec<(PathBuf, c_uint)>,
+    preprocessor: Option<Preprocessor>,
+    portable: bool,
+    attribute_locations: Vec<(String, u32)>,
+}
+
+fn read_source(path: &Path) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|e| Error::Io {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ProgramBuilder {
+    pub fn new() -> ProgramBuilder {
+        ProgramBuilder::default()
+    }
+
+    pub fn shader<P: Into<PathBuf>>(mut self, path: P, kind: c_uint) -> Self {
+        self.shaders.push((path.into(), kind));
+        self
+    }
+
+    pub fn vertex<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.shader(path, glow::VERTEX_SHADER)
+    }
+
+    pub fn fragment<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.shader(path, glow::FRAGMENT_SHADER)
+    }
+
+    // Runs every shader through `preprocessor` before compiling it
+    pub fn preprocessor(mut self, preprocessor: Preprocessor) -> Self {
+        self.preprocessor = Some(preprocessor);
+        self
+    }
+
+    // Gives shaders without a #version the header of the context, see
+    // Shader::from_portable_source
+    pub fn portable(mut self) -> Self {
+        self.portable = true;
+        self
+    }
+
+    // Binds the fixed attribute locations of V, see
+    // Program::from_shaders_with_layout
+    pub fn vertex_layout<V: VertexLayout>(mut self) -> Self {
+        self.attribute_locations
+            .extend(fixed_locations(&V::attributes()));
+        self
+    }
+
+    pub fn build(&self, gl: &GL) -> Result<Program, Error> {
+        self.build_watched(gl, &mut vec![])
+    }
+
+    pub fn watch(self, gl: &GL) -> Result<ReloadableProgram, Error> {
+        let mut watched = vec![];
+        let program = self.build_watched(gl, &mut watched)?;
+        Ok(ReloadableProgram {
+            gl: gl.clone(),
+            builder: self,
+            program,
+            modified: modified_times(&watched),
+            watched,
+        })
+    }
+
+    // Builds the program and collects every file it was read from into
+    // `watched`: the shaders and the files the preprocessor included from
+    // disk. The files read before a failure are collected as well, so that
+    // fixing them triggers a reload.
+    fn build_watched(
+        &self,
+        gl: &GL,
+        watched: &mut Vec<PathBuf>,
+    ) -> Result<Program, Error> {
+        let portable = self.portable.then(|| {
+            let mut preprocessor =
+                self.preprocessor.clone().unwrap_or_default();
+            preprocessor.version(gl.glsl_version());
+            preprocessor
+        });
+        let preprocessor = portable.as_ref().or(self.preprocessor.as_ref());
+        let mut shaders = vec![];
+        watched.extend(self.shaders.iter().map(|(path, _)| path.clone()));
+        let mut watch = |path: &PathBuf| {
+            if !watched.contains(path) {
+                watched.push(path.clone());
+            }
+        };
+        for (path, kind) in self.shaders.iter() {
+            let source = read_source(path)?;
+            let shader = match preprocessor {
+                Some(preprocessor) => {
+                    let name = path.display().to_string();
+                    let result = preprocessor.process(&name, &source);
+                    // an included file that failed to resolve its own
+                    // includes
+                    if let Err(Error::Preprocess { file, .. }) = &result {
+                        if let Some(path) = preprocessor.path_of(file) {
+                            watch(&path);
+                        }
+                    }
+                    let processed = result?;
+                    for file in processed.files.iter() {
+                        if let Some(path) = &file.path {
+                            watch(path);
+                        }
+                    }
+                    Shader::from_processed(gl, &processed, *kind)?
+                }
+                None => Shader::from_source(gl, &source, *kind)?,
+            };
+            shaders.push(shader);
+        }
+        let locations: Vec<(&str, u32)> = self
+            .attribute_locations
+            .iter()
+            .map(|(name, location)| (name.as_str(), *location))
+            .collect();
+        Program::from_shaders_with_locations(gl, &shaders, &locations)
+    }
+}
+
+fn modified_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths.iter().map(|path| modified(path)).collect()
+}
+
+// Derefs to the current program. Uniform handles taken from it have to be
+// fetched again after a successful reload, Program::set rejects old ones.
+pub struct ReloadableProgram {
+    gl: GL,
+    builder: ProgramBuilder,
+    program: Program,
+    // files of the last build and their modification times
+    watched: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl ReloadableProgram {
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    // Polls modification times of the shaders and their included files
+    // and rebuilds the program if any changed. Ok(true) means the program
+    // was swapped, on error the old program stays in use.
+    pub fn reload_if_changed(&mut self) -> Result<bool, Error> {
+        let modified = modified_times(&self.watched);
+        if modified == self.modified {
+            return Ok(false);
+        }
+        // don't retry a broken file until it is saved again
+        self.modified = modified;
+        self.reload()?;
+        Ok(true)
+    }
+
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let mut watched = vec![];
+        let result = self.builder.build_watched(&self.gl, &mut watched);
+        // includes may have been added or removed, even by a failed build
+        self.modified = modified_times(&watched);
+        self.watched = watched;
+        let mut program = result?;
+        program.set_missing_uniform(self.program.missing_uniform());
+        self.program = program;
+        Ok(())
+    }
+}
+
+impl Deref for ReloadableProgram {
+    type Target = Program;
+    fn deref(&self) -> &Program {
+        &self.program
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+    use std::fs::File;
+    use std::time::Duration;
+
+    // moves the modification time forward instead of waiting for the
+    // clock to tick
+    fn touch(path: &Path, seconds: u64) {
+        let file = File::options().write(true).open(path).unwrap();
+        let time = SystemTime::now() + Duration::from_secs(seconds);
+        file.set_modified(time).unwrap();
+    }
+
+    #[test]
+    fn included_files_are_watched() {
+        let dir = std::env::temp_dir()
+            .join(format!("red-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let shader = dir.join("main.vert");
+        let include = dir.join("common.glsl");
+        std::fs::write(&shader, "#include \"common.glsl\"\n").unwrap();
+        std::fs::write(&include, "float x;\n").unwrap();
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.include_dir(&dir);
+        let mut program = ProgramBuilder::new()
+            .vertex(&shader)
+            .preprocessor(preprocessor)
+            .watch(&gl)
+            .unwrap();
+        assert_eq!(program.watched, [shader.clone(), include.clone()]);
+        assert!(!program.reload_if_changed().unwrap());
+        touch(&include, 10);
+        let id = program.id();
+        assert!(program.reload_if_changed().unwrap());
+        assert_ne!(program.id(), id);
+        // a broken include keeps the program until it is saved again
+        recorder.script_compile(false, "0:1(1): error: broken\n");
+        touch(&include, 20);
+        let id = program.id();
+        assert!(program.reload_if_changed().is_err());
+        assert_eq!(program.id(), id);
+        assert!(!program.reload_if_changed().unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_builds_watch_new_includes() {
+        let dir = std::env::temp_dir()
+            .join(format!("red-reload-new-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let shader = dir.join("main.vert");
+        let include = dir.join("common.glsl");
+        let nested = dir.join("nested.glsl");
+        std::fs::write(&shader, "float x;\n").unwrap();
+        std::fs::write(&include, "#include \"missing.glsl\"\n").unwrap();
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        let mut preprocessor = Preprocessor::new();
+        preprocessor.include_dir(&dir);
+        let mut program = ProgramBuilder::new()
+            .vertex(&shader)
+            .preprocessor(preprocessor)
+            .watch(&gl)
+            .unwrap();
+        // the new include can't resolve its own include
+        std::fs::write(&shader, "#include \"common.glsl\"\n").unwrap();
+        touch(&shader, 10);
+        assert!(program.reload_if_changed().is_err());
+        assert_eq!(program.watched, [shader.clone(), include.clone()]);
+        // fixing it reloads, the new nested include fails to compile
+        std::fs::write(&include, "#include \"nested.glsl\"\n").unwrap();
+        std::fs::write(&nested, "broken\n").unwrap();
+        touch(&include, 10);
+        recorder.script_compile(false, "0:1(1): error: broken\n");
+        assert!(program.reload_if_changed().is_err());
+        assert_eq!(program.watched, [shader.clone(), include, nested.clone()]);
+        touch(&nested, 10);
+        let id = program.id();
+        assert!(program.reload_if_changed().unwrap());
+        assert_ne!(program.id(), id);
+        // a direct reload stores the current times as well
+        touch(&shader, 20);
+        program.reload().unwrap();
+        assert!(!program.reload_if_changed().unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,144 +1,150 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
 use std::os::raw::{c_int, c_uint};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use super::buffer::{VertexArray, VertexBufferBehavior};
-use super::GL;
+use super::data::{VertexAttribute, VertexLayout};
+use super::diagnostic;
+use super::preprocessor::{Preprocessor, Processed};
+use super::reflect::{
+    self, AttributeInfo, BaseType, UniformInfo, VertexReport,
+};
+pub use super::texture::{SampledTexture, Sampler, Texture, TextureUnit};
+use super::{Error, ShaderStage, GL};
 use glow;
-use glow::native::Context as GL_Context;
-use glow::Context;
 
-#[derive(Clone, Debug)]
-pub struct Texture {
-    pub texture: <GL_Context as Context>::Texture,
-    w: u32,
-    h: u32,
+// Identifies programs for Uniform handles, GL may reuse deleted names
+static NEXT_SERIAL: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Program {
+    gl: GL,
+    id: c_uint,
+    serial: usize,
+    // filled from the active uniforms after linking, misses are looked up
+    // once and remembered as well
+    uniform_locations: RefCell<HashMap<String, Option<c_uint>>>,
+    missing_uniform: MissingUniform,
+    warned_uniforms: RefCell<HashSet<String>>,
+    // None when the backend can't list attributes
+    attributes: Option<Vec<AttributeInfo>>,
+    uniforms: Vec<UniformInfo>,
+    // texture unit of every sampler uniform location, assigned in order
+    // after linking
+    texture_units: RefCell<TextureUnits>,
 }
 
-impl PartialEq for Texture {
-    fn eq(&self, other: &Texture) -> bool {
-        self.texture == other.texture
-    }
+#[derive(Debug, Default)]
+struct TextureUnits {
+    units: HashMap<c_uint, u32>,
+    // locations a texture was bound for, their units don't move anymore
+    bound: HashSet<c_uint>,
+    // GL::max_texture_units
+    limit: u32,
 }
 
-impl Texture {
-    pub fn from_rgba8(
-        gl_ctx: &GL,
-        width: u32,
-        height: u32,
-        bytes: &[u8],
-    ) -> Texture {
-        unsafe {
-            let texture = gl_ctx.create_texture().unwrap();
-            gl_ctx.active_texture(glow::TEXTURE0);
-            gl_ctx.bind_texture(glow::TEXTURE_2D, Some(texture));
-            gl_ctx.tex_image_2d(
-                glow::TEXTURE_2D,
-                0,
-                glow::RGBA as i32,
-                width as i32,
-                height as i32,
-                0,
-                glow::RGBA,
-                glow::UNSIGNED_BYTE,
-                Some(bytes),
-            );
-            gl_ctx.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_WRAP_S,
-                glow::CLAMP_TO_EDGE as i32,
-            );
-            gl_ctx.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_WRAP_T,
-                glow::CLAMP_TO_EDGE as i32,
-            );
-            gl_ctx.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MAG_FILTER,
-                glow::NEAREST as i32,
-            );
-            gl_ctx.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MIN_FILTER,
-                glow::NEAREST as i32,
-            );
-            gl_ctx.bind_texture(glow::TEXTURE_2D, None);
-            Texture {
-                texture,
-                w: width,
-                h: height,
-            }
-        }
+impl TextureUnits {
+    fn free_unit(&self, reserved: Option<u32>) -> Result<u32, Error> {
+        (0..self.limit)
+            .find(|unit| {
+                Some(*unit) != reserved
+                    && !self.units.values().any(|used| used == unit)
+            })
+            .ok_or(Error::NoFreeTextureUnit(self.limit))
     }
 
-    pub fn dimensions(&self) -> (u32, u32) {
-        (self.w, self.h)
+    fn assigned(&mut self, location: c_uint) -> Result<u32, Error> {
+        let unit = match self.units.get(&location) {
+            Some(unit) => *unit,
+            None => self.free_unit(None)?,
+        };
+        self.units.insert(location, unit);
+        self.bound.insert(location);
+        Ok(unit)
     }
 
-    pub fn new(gl_ctx: &GL, (width, height): (u32, u32)) -> Self {
-        let mut name = 0;
-        unsafe {
-            // Create a texture for the glyphs
-            // The texture holds 1 byte per pixel as alpha data
-            gl_ctx.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
-            let name = gl_ctx.create_texture().unwrap();
-            // gl_ctx.GenTextures(1, &mut name);
-            gl_ctx.bind_texture(glow::TEXTURE_2D, Some(name));
-            // gl_ctx.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as _);
-            // gl_ctx.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as _);
-            gl_ctx.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MIN_FILTER,
-                glow::LINEAR as _,
-            );
-            gl_ctx.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MAG_FILTER,
-                glow::NEAREST as _,
-            );
-            gl_ctx.tex_image_2d(
-                glow::TEXTURE_2D,
-                0,
-                glow::RGB as _,
-                width as _,
-                height as _,
-                0,
-                glow::RGBA,
-                glow::UNSIGNED_BYTE,
-                None,
-            );
-            gl_ctx.bind_texture(glow::TEXTURE_2D, None);
-            // gl_assert_ok!();
-
-            Self {
-                texture: name,
-                w: width,
-                h: height,
+    // Moves samplers that were assigned `unit` but not bound yet
+    fn explicit(&mut self, location: c_uint, unit: u32) -> Result<(), Error> {
+        let taken: Vec<c_uint> = self
+            .units
+            .iter()
+            .filter(|(other, used)| **other != location && **used == unit)
+            .map(|(other, _)| *other)
+            .collect();
+        for other in taken {
+            if self.bound.contains(&other) {
+                return Err(Error::TextureUnitTaken(unit));
             }
+            let free = self.free_unit(Some(unit))?;
+            self.units.insert(other, free);
         }
+        self.units.insert(location, unit);
+        self.bound.insert(location);
+        Ok(())
     }
 }
 
-// TODO
-// impl Drop for Texture {
-//     fn drop(&mut self) {
-//         unsafe {
+// What set_uniform does when the name is not an active uniform, e.g. after
+// the driver optimized it out
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum MissingUniform {
+    #[default]
+    Panic,
+    // logs a warning with the `log` crate the first time per name
+    WarnOnce,
+    Ignore,
+}
 
-//         }
-//     }
-// }
+// Location of a uniform in one program, set with `Program::set`
+pub struct Uniform<T> {
+    // Program::serial of the program it was taken from
+    program: usize,
+    location: c_uint,
+    _phantom_data: PhantomData<fn(T)>,
+}
 
-pub struct Program {
-    gl: GL,
-    id: c_uint,
+impl<T> Clone for Uniform<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Uniform<T> {}
+
+impl<T> std::fmt::Debug for Uniform<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Uniform")
+            .field("program", &self.program)
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+impl<T> Uniform<T> {
+    pub fn location(&self) -> c_uint {
+        self.location
+    }
 }
 
 // Fedor(not-fl'3)'s uniforms code
 pub trait UniformValue: Clone + PartialEq {
-    fn set(self, gl: &GL, location: <GL_Context as Context>::UniformLocation);
+    fn set(self, gl: &GL, location: c_uint);
+
+    // What Program::set and set_uniform call, samplers use it to get the
+    // texture unit of their location
+    fn set_for_program(
+        self,
+        program: &Program,
+        location: c_uint,
+    ) -> Result<(), Error> {
+        self.set(&program.gl, location);
+        Ok(())
+    }
 }
 
 impl UniformValue for (f32, f32, f32, f32) {
-    fn set(self, gl: &GL, location: <GL_Context as Context>::UniformLocation) {
+    fn set(self, gl: &GL, location: c_uint) {
         unsafe {
             gl.uniform_4_f32(Some(location), self.0, self.1, self.2, self.3);
         }
@@ -146,7 +152,7 @@ impl UniformValue for (f32, f32, f32, f32) {
 }
 
 impl UniformValue for (f32, f32, f32) {
-    fn set(self, gl: &GL, location: <GL_Context as Context>::UniformLocation) {
+    fn set(self, gl: &GL, location: c_uint) {
         unsafe {
             gl.uniform_3_f32(Some(location), self.0, self.1, self.2);
         }
@@ -154,7 +160,7 @@ impl UniformValue for (f32, f32, f32) {
 }
 
 impl UniformValue for (f32, f32) {
-    fn set(self, gl: &GL, location: <GL_Context as Context>::UniformLocation) {
+    fn set(self, gl: &GL, location: c_uint) {
         unsafe {
             gl.uniform_2_f32(Some(location), self.0, self.1);
         }
@@ -162,7 +168,7 @@ impl UniformValue for (f32, f32) {
 }
 
 impl UniformValue for f32 {
-    fn set(self, gl: &GL, location: <GL_Context as Context>::UniformLocation) {
+    fn set(self, gl: &GL, location: c_uint) {
         unsafe {
             gl.uniform_1_f32(Some(location), self);
         }
@@ -170,7 +176,7 @@ impl UniformValue for f32 {
 }
 
 impl UniformValue for [[f32; 4]; 4] {
-    fn set(self, gl: &GL, location: <GL_Context as Context>::UniformLocation) {
+    fn set(self, gl: &GL, location: c_uint) {
         unsafe {
             // transmute here according to
             // https://users.rust-lang.org/t/converting-f32-4-4-to-f32-16/22391
@@ -185,19 +191,104 @@ impl UniformValue for [[f32; 4]; 4] {
 }
 
 impl UniformValue for [f32; 16] {
-    fn set(self, gl: &GL, location: <GL_Context as Context>::UniformLocation) {
+    fn set(self, gl: &GL, location: c_uint) {
         unsafe { gl.uniform_matrix_4_f32_slice(Some(location), false, &self) }
     }
 }
 
+unsafe fn bind_texture_unit(
+    gl: &GL,
+    location: c_uint,
+    texture: c_uint,
+    unit: u32,
+    sampler: Option<&Sampler>,
+) {
+    gl.active_texture(glow::TEXTURE0 + unit);
+    gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+    match sampler {
+        Some(sampler) => sampler.bind(gl, unit),
+        // a sampler left on the unit would override the texture's own state
+        None if gl.supports_samplers() => gl.bind_sampler(unit, None),
+        None => (),
+    }
+    gl.uniform_1_i32(Some(location), unit as i32);
+}
+
+// Outside of a program the texture goes to unit 0
 impl UniformValue for Texture {
-    fn set(self, gl: &GL, location: <GL_Context as Context>::UniformLocation) {
+    fn set(self, gl: &GL, location: c_uint) {
+        unsafe { bind_texture_unit(gl, location, self.texture, 0, None) }
+    }
+
+    fn set_for_program(
+        self,
+        program: &Program,
+        location: c_uint,
+    ) -> Result<(), Error> {
+        let unit = program.texture_unit(location)?;
+        unsafe {
+            bind_texture_unit(&program.gl, location, self.texture, unit, None)
+        }
+        Ok(())
+    }
+}
+
+impl UniformValue for SampledTexture {
+    fn set(self, gl: &GL, location: c_uint) {
+        let (texture, unit) = (self.texture.texture, self.unit.unwrap_or(0));
+        unsafe {
+            bind_texture_unit(gl, location, texture, unit, Some(&self.sampler))
+        }
+    }
+
+    fn set_for_program(
+        self,
+        program: &Program,
+        location: c_uint,
+    ) -> Result<(), Error> {
+        let unit = match self.unit {
+            Some(unit) => {
+                program.reserve_texture_unit(location, unit)?;
+                unit
+            }
+            None => program.texture_unit(location)?,
+        };
+        let texture = self.texture.texture;
+        unsafe {
+            bind_texture_unit(
+                &program.gl,
+                location,
+                texture,
+                unit,
+                Some(&self.sampler),
+            )
+        }
+        Ok(())
+    }
+}
+
+impl UniformValue for TextureUnit {
+    fn set(self, gl: &GL, location: c_uint) {
         unsafe {
-            gl.active_texture(glow::TEXTURE0);
-            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
-            gl.uniform_1_i32(Some(location), 0);
+            bind_texture_unit(
+                gl,
+                location,
+                self.texture.texture,
+                self.unit,
+                None,
+            )
         }
     }
+
+    fn set_for_program(
+        self,
+        program: &Program,
+        location: c_uint,
+    ) -> Result<(), Error> {
+        program.reserve_texture_unit(location, self.unit)?;
+        self.set(&program.gl, location);
+        Ok(())
+    }
 }
 
 impl Program {
@@ -206,32 +297,70 @@ impl Program {
         gl: &GL,
         vao: &VertexArray,
         vbos: &[&dyn VertexBufferBehavior],
-    ) {
+    ) -> Result<(), Error> {
         vao.bind();
-        for vbo in vbos.iter() {
+        let result = vbos.iter().try_for_each(|vbo| {
             vbo.bind();
-            vbo.vertex_attrib_pointers(&gl, &self);
+            let result = vbo.vertex_attrib_pointers(&gl, &self);
             vbo.unbind();
-        }
+            result
+        });
         vao.unbind();
+        result?;
+        // non-indexed draws of `vao` take the count of the first buffer,
+        // including later uploads
+        if let Some(vbo) = vbos.first() {
+            vao.share_vertex_count(vbo.vertex_count());
+        }
+        Ok(())
+    }
+
+    // Locations are left to the driver, use from_shaders_with_layout for
+    // vertex types with #[location] fields
+    pub fn from_shaders(gl: &GL, shaders: &[Shader]) -> Result<Program, Error> {
+        Program::from_shaders_with_locations(gl, shaders, &[])
+    }
+
+    // Binds the #[location = "N"] fields of V before linking, so one vertex
+    // type feeds every program built this way
+    pub fn from_shaders_with_layout<V: VertexLayout>(
+        gl: &GL,
+        shaders: &[Shader],
+    ) -> Result<Program, Error> {
+        let locations = fixed_locations(&V::attributes());
+        let locations: Vec<(&str, u32)> = locations
+            .iter()
+            .map(|(name, location)| (name.as_str(), *location))
+            .collect();
+        Program::from_shaders_with_locations(gl, shaders, &locations)
     }
 
-    pub fn from_shaders(
+    // (attribute name, location) pairs bound before linking
+    pub fn from_shaders_with_locations(
         gl: &GL,
         shaders: &[Shader],
-    ) -> Result<Program, String> {
-        let program_id = unsafe { gl.create_program()? };
+        locations: &[(&str, u32)],
+    ) -> Result<Program, Error> {
+        let program_id =
+            unsafe { gl.create_program().map_err(Error::ObjectCreation)? };
 
         for shader in shaders {
             unsafe {
                 gl.attach_shader(program_id, shader.id());
             }
         }
+        for (name, location) in locations {
+            unsafe {
+                gl.bind_attrib_location(program_id, *location, name);
+            }
+        }
 
         unsafe {
             gl.link_program(program_id);
             if !gl.get_program_link_status(program_id) {
-                return Err(gl.get_program_info_log(program_id));
+                let log = gl.get_program_info_log(program_id);
+                gl.delete_program(program_id);
+                return Err(Error::Link(log));
             }
         }
         for shader in shaders {
@@ -241,9 +370,23 @@ impl Program {
             }
         }
 
+        let attributes = unsafe { reflect::active_attributes(gl, program_id) };
+        let uniforms = unsafe { reflect::active_uniforms(gl, program_id) };
+        let texture_units = unsafe { texture_units(gl, program_id, &uniforms) };
         Ok(Program {
             gl: gl.clone(),
             id: program_id,
+            serial: NEXT_SERIAL.fetch_add(1, Ordering::Relaxed),
+            uniform_locations: RefCell::new(uniform_locations(&uniforms)),
+            missing_uniform: MissingUniform::default(),
+            warned_uniforms: RefCell::new(HashSet::new()),
+            attributes,
+            uniforms,
+            texture_units: RefCell::new(TextureUnits {
+                units: texture_units,
+                bound: HashSet::new(),
+                limit: gl.max_texture_units(),
+            }),
         })
     }
 
@@ -251,19 +394,139 @@ impl Program {
         self.id
     }
 
+    // Sorted by location. Error::Unsupported when the backend can't list
+    // attributes, see backend::Native::new.
+    pub fn attributes(&self) -> Result<&[AttributeInfo], Error> {
+        self.attributes.as_deref().ok_or_else(|| {
+            Error::Unsupported(
+                "listing active attributes needs glGetActiveAttrib".to_string(),
+            )
+        })
+    }
+
+    pub fn attribute(
+        &self,
+        name: &str,
+    ) -> Result<Option<&AttributeInfo>, Error> {
+        Ok(self
+            .attributes()?
+            .iter()
+            .find(|attribute| attribute.name == name))
+    }
+
+    // Checks the fields of a derived vertex type against the active
+    // attributes; the derive panics at draw time on inactive fields.
+    // Error::Unsupported when the backend can't list attributes.
+    pub fn validate_vertex<V: VertexLayout>(
+        &self,
+    ) -> Result<VertexReport, Error> {
+        Ok(reflect::validate_vertex(
+            self.attributes()?,
+            &V::attributes(),
+        ))
+    }
+
+    // Unit a Texture set to the sampler at `location` is bound to. Samplers
+    // the reflection missed get the lowest free unit,
+    // Error::NoFreeTextureUnit when there is none left.
+    pub fn texture_unit(&self, location: c_uint) -> Result<u32, Error> {
+        self.texture_units.borrow_mut().assigned(location)
+    }
+
+    // Keeps assigned units off `unit` from now on, errors when a texture
+    // was already bound to it for another sampler
+    fn reserve_texture_unit(
+        &self,
+        location: c_uint,
+        unit: u32,
+    ) -> Result<(), Error> {
+        self.texture_units.borrow_mut().explicit(location, unit)
+    }
+
+    pub fn uniforms(&self) -> &[UniformInfo] {
+        &self.uniforms
+    }
+
+    pub fn uniform_info(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.iter().find(|uniform| uniform.name == name)
+    }
+
     pub fn set_used(&self) {
         unsafe {
             self.gl.use_program(Some(self.id));
         }
     }
 
-    pub fn set_uniform<T: UniformValue>(&self, name: &str, uniform: T) {
+    pub fn uniform_location(&self, name: &str) -> Option<c_uint> {
+        if let Some(location) = self.uniform_locations.borrow().get(name) {
+            return *location;
+        }
+        let location = unsafe { self.gl.get_uniform_location(self.id, name) };
+        self.uniform_locations
+            .borrow_mut()
+            .insert(name.to_string(), location);
+        location
+    }
+
+    pub fn uniform<T: UniformValue>(&self, name: &str) -> Option<Uniform<T>> {
+        self.uniform_location(name).map(|location| Uniform {
+            program: self.serial,
+            location,
+            _phantom_data: PhantomData,
+        })
+    }
+
+    // Handles kept from before a reload belong to the old program and
+    // are rejected
+    pub fn set<T: UniformValue>(
+        &self,
+        uniform: Uniform<T>,
+        value: T,
+    ) -> Result<(), Error> {
+        if uniform.program != self.serial {
+            return Err(Error::ForeignUniform);
+        }
+        self.set_used();
+        value.set_for_program(self, uniform.location)
+    }
+
+    pub fn set_missing_uniform(&mut self, missing_uniform: MissingUniform) {
+        self.missing_uniform = missing_uniform;
+    }
+
+    pub fn missing_uniform(&self) -> MissingUniform {
+        self.missing_uniform
+    }
+
+    pub fn try_set_uniform<T: UniformValue>(
+        &self,
+        name: &str,
+        uniform: T,
+    ) -> Result<(), Error> {
+        let location = self
+            .uniform_location(name)
+            .ok_or_else(|| Error::MissingUniform(name.to_string()))?;
         self.set_used();
-        let location = unsafe {
-            self.gl.get_uniform_location(self.id(), name)
-            .expect(&format!("name \"{}\" does not correspond to an active uniform variable in program or name starts with the reserved prefix \"gl_\"", name))
+        uniform.set_for_program(self, location)
+    }
+
+    // Panics on errors other than a missing uniform, use try_set_uniform
+    // to handle them
+    pub fn set_uniform<T: UniformValue>(&self, name: &str, uniform: T) {
+        let error = match self.try_set_uniform(name, uniform) {
+            Ok(()) => return,
+            Err(error @ Error::MissingUniform(_)) => error,
+            Err(error) => panic!("{}", error),
         };
-        uniform.set(&self.gl, location);
+        match self.missing_uniform {
+            MissingUniform::Panic => panic!("{}", error),
+            MissingUniform::WarnOnce => {
+                if self.warned_uniforms.borrow_mut().insert(name.to_string()) {
+                    log::warn!("{}", error);
+                }
+            }
+            MissingUniform::Ignore => (),
+        }
     }
 }
 
@@ -275,6 +538,73 @@ impl Drop for Program {
     }
 }
 
+// (major, minor) of a desktop GL_VERSION string
+fn gl_version_number(version: &str) -> Option<(u32, u32)> {
+    let number = version.split_whitespace().next()?;
+    let mut parts = number.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+// GLSL dialect a portable shader is compiled as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlslVersion {
+    // desktop GL 3.3 core
+    Glsl330,
+    // GLES 3 and WebGL 2
+    Es300,
+    // GLES 2 and WebGL 1
+    Es100,
+}
+
+impl GlslVersion {
+    // Parses a GL_VERSION string, e.g. "4.6.0 NVIDIA 440.82",
+    // "OpenGL ES 3.2 Mesa 20.0.8" or "WebGL 2.0". Desktop contexts older
+    // than 3.3 are still mapped to Glsl330, portable shaders need it.
+    pub fn from_gl_version(version: &str) -> GlslVersion {
+        let major = |rest: &str| rest.trim_start().chars().next();
+        if let Some(rest) = version.strip_prefix("OpenGL ES") {
+            match major(rest) {
+                Some('1') | Some('2') => GlslVersion::Es100,
+                _ => GlslVersion::Es300,
+            }
+        } else if let Some(rest) = version.strip_prefix("WebGL") {
+            match major(rest) {
+                Some('1') => GlslVersion::Es100,
+                _ => GlslVersion::Es300,
+            }
+        } else {
+            GlslVersion::Glsl330
+        }
+    }
+
+    // Whether a context with this GL_VERSION has sampler objects: GL 3.3
+    // and GLES 3, desktop 3.0 to 3.2 don't
+    pub(crate) fn has_samplers(version: &str) -> bool {
+        match GlslVersion::from_gl_version(version) {
+            GlslVersion::Es100 => false,
+            GlslVersion::Es300 => true,
+            GlslVersion::Glsl330 => {
+                gl_version_number(version).is_none_or(|number| number >= (3, 3))
+            }
+        }
+    }
+
+    pub fn header(self) -> &'static str {
+        match self {
+            GlslVersion::Glsl330 => "#version 330 core\n",
+            GlslVersion::Es300 => concat!(
+                "#version 300 es\n",
+                "precision highp float;\n",
+                "precision highp int;\n"
+            ),
+            // highp is optional in GLES 2 fragment shaders
+            GlslVersion::Es100 => "#version 100\nprecision mediump float;\n",
+        }
+    }
+}
+
 pub struct Shader {
     gl: GL,
     id: c_uint,
@@ -285,16 +615,45 @@ impl Shader {
         gl: &GL,
         source: &str,
         kind: c_uint,
-    ) -> Result<Shader, String> {
-        let id = shader_from_source(gl, source, kind)?;
+    ) -> Result<Shader, Error> {
+        let id = shader_from_source(gl, source, kind, &[("", source)])?;
         Ok(Shader { gl: gl.clone(), id })
     }
 
-    pub fn from_vert_source(gl: &GL, source: &str) -> Result<Shader, String> {
+    // Prepends the #version and precision header of the current context
+    // unless the source starts with its own #version. The same as
+    // processing `source` with Preprocessor::version, which also combines
+    // the header with includes and defines.
+    pub fn from_portable_source(
+        gl: &GL,
+        source: &str,
+        kind: c_uint,
+    ) -> Result<Shader, Error> {
+        let processed = Preprocessor::new()
+            .version(gl.glsl_version())
+            .process("", source)?;
+        Shader::from_processed(gl, &processed, kind)
+    }
+
+    pub fn from_processed(
+        gl: &GL,
+        processed: &Processed,
+        kind: c_uint,
+    ) -> Result<Shader, Error> {
+        let files: Vec<(&str, &str)> = processed
+            .files
+            .iter()
+            .map(|file| (file.name.as_str(), file.source.as_str()))
+            .collect();
+        let id = shader_from_source(gl, &processed.source, kind, &files)?;
+        Ok(Shader { gl: gl.clone(), id })
+    }
+
+    pub fn from_vert_source(gl: &GL, source: &str) -> Result<Shader, Error> {
         Shader::from_source(gl, source, glow::VERTEX_SHADER)
     }
 
-    pub fn from_frag_source(gl: &GL, source: &str) -> Result<Shader, String> {
+    pub fn from_frag_source(gl: &GL, source: &str) -> Result<Shader, Error> {
         Shader::from_source(gl, source, glow::FRAGMENT_SHADER)
     }
 
@@ -311,21 +670,282 @@ impl Drop for Shader {
     }
 }
 
+pub(crate) fn fixed_locations(
+    attributes: &[VertexAttribute],
+) -> Vec<(String, u32)> {
+    attributes
+        .iter()
+        .filter_map(|attribute| {
+            Some((attribute.name.to_string(), attribute.location?))
+        })
+        .collect()
+}
+
+unsafe fn texture_units(
+    gl: &GL,
+    program: c_uint,
+    uniforms: &[UniformInfo],
+) -> HashMap<c_uint, u32> {
+    let mut units = HashMap::new();
+    let samplers = uniforms
+        .iter()
+        .filter(|uniform| uniform.kind.base_type() == BaseType::Sampler);
+    for uniform in samplers {
+        for index in 0..uniform.size {
+            let location = if uniform.size > 1 {
+                let name = format!("{}[{}]", uniform.name, index);
+                gl.get_uniform_location(program, &name)
+            } else {
+                uniform.location
+            };
+            if let Some(location) = location {
+                let next = units.len() as u32;
+                units.insert(location, next);
+            }
+        }
+    }
+    units
+}
+
+fn uniform_locations(
+    uniforms: &[UniformInfo],
+) -> HashMap<String, Option<c_uint>> {
+    let mut locations = HashMap::new();
+    for uniform in uniforms {
+        // arrays can be set both as "name" and "name[0]"
+        if uniform.size > 1 {
+            locations.insert(format!("{}[0]", uniform.name), uniform.location);
+        }
+        locations.insert(uniform.name.clone(), uniform.location);
+    }
+    locations
+}
+
+// `files` are the (name, source) pairs driver log lines refer to
 fn shader_from_source(
     gl: &GL,
     source: &str,
     kind: c_uint,
-) -> Result<c_uint, String> {
-    let id = unsafe { gl.create_shader(kind).expect("Cannot create program") };
+    files: &[(&str, &str)],
+) -> Result<c_uint, Error> {
+    let id = unsafe { gl.create_shader(kind).map_err(Error::ObjectCreation)? };
+    if id == 0 {
+        return Err(Error::ObjectCreation(format!(
+            "cannot create {} shader",
+            ShaderStage::from(kind)
+        )));
+    }
     unsafe {
         gl.shader_source(id, source);
         gl.compile_shader(id);
     }
     unsafe {
         if !gl.get_shader_compile_status(id) {
-            let error = gl.get_shader_info_log(id);
-            return Err(error);
+            let log = gl.get_shader_info_log(id);
+            gl.delete_shader(id);
+            let mut diagnostics = diagnostic::parse_log(&log);
+            diagnostic::locate(&mut diagnostics, files);
+            return Err(Error::ShaderCompile {
+                stage: ShaderStage::from(kind),
+                log,
+                diagnostics,
+            });
         }
     }
     Ok(id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::{Event, Recorder};
+
+    #[test]
+    fn compile_failure_is_located_in_the_source() {
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        recorder.script_compile(false, "0:3(5): error: `foo' undeclared\n");
+        match Shader::from_frag_source(&gl, "a\nb\nfoo;\n") {
+            Err(Error::ShaderCompile { diagnostics, .. }) => {
+                assert_eq!(diagnostics.len(), 1);
+                assert_eq!(diagnostics[0].line, Some(3));
+                assert_eq!(diagnostics[0].column, Some(5));
+                assert_eq!(diagnostics[0].code.as_deref(), Some("foo;"));
+            }
+            other => panic!("unexpected {:?}", other.map(|s| s.id())),
+        }
+        // the next compile is back to the default
+        assert!(Shader::from_frag_source(&gl, "").is_ok());
+    }
+
+    #[test]
+    fn sampler_objects_need_gl_3_3_or_gles_3() {
+        let versions = [
+            ("4.6.0 NVIDIA 440.82", true),
+            ("3.3 (Core Profile) Mesa 20.0.8", true),
+            ("3.2.0 Mesa 20.0.8", false),
+            ("3.0 Mesa 20.0.8", false),
+            ("OpenGL ES 3.0 Mesa 20.0.8", true),
+            ("OpenGL ES 2.0 Mesa 20.0.8", false),
+            ("WebGL 2.0", true),
+            ("WebGL 1.0", false),
+        ];
+        for (version, samplers) in versions.iter() {
+            assert_eq!(
+                GlslVersion::has_samplers(version),
+                *samplers,
+                "{}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn portable_source_gets_the_context_header() {
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        Shader::from_portable_source(&gl, "x;\n", glow::VERTEX_SHADER).unwrap();
+        Shader::from_portable_source(
+            &gl,
+            "#version 100\nx;",
+            glow::VERTEX_SHADER,
+        )
+        .unwrap();
+        let sources: Vec<String> = recorder
+            .events()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::ShaderSource { source, .. } => Some(source),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                "#version 330 core\n#line 1 0\nx;\n",
+                "#version 100\n#line 1 0\nx;\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn uniforms_are_rejected_by_other_programs() {
+        let gl = GL::from_backend(Recorder::new());
+        let program = || {
+            let shader = Shader::from_vert_source(&gl, "").unwrap();
+            Program::from_shaders(&gl, &[shader]).unwrap()
+        };
+        let (old, new) = (program(), program());
+        let scale = old.uniform::<f32>("scale").unwrap();
+        assert!(old.set(scale, 2.0).is_ok());
+        assert!(matches!(new.set(scale, 2.0), Err(Error::ForeignUniform)));
+        let scale = new.uniform::<f32>("scale").unwrap();
+        assert!(new.set(scale, 2.0).is_ok());
+    }
+
+    // program with `diffuse` and `normal` samplers and the units their
+    // sampler uniforms get set to
+    fn two_samplers() -> (Recorder, Program, Texture) {
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        let sampler = |name: &str| glow::ActiveUniform {
+            size: 1,
+            utype: glow::SAMPLER_2D,
+            name: name.to_string(),
+        };
+        recorder
+            .set_active_uniforms(vec![sampler("diffuse"), sampler("normal")]);
+        let shader = Shader::from_frag_source(&gl, "").unwrap();
+        let program = Program::from_shaders(&gl, &[shader]).unwrap();
+        let texture = Texture::from_rgba8(&gl, 1, 1, &[0; 4]).unwrap();
+        (recorder, program, texture)
+    }
+
+    fn sampler_units(recorder: &Recorder, program: &Program) -> Vec<i32> {
+        let location = |name| program.uniform_location(name);
+        let names = [location("diffuse"), location("normal")];
+        recorder
+            .take_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Uniform1I32 { location, x }
+                    if names.contains(&location) =>
+                {
+                    Some(x)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn samplers_get_their_own_units() {
+        let (recorder, program, texture) = two_samplers();
+        recorder.clear();
+        program.set_uniform("diffuse", texture.clone());
+        program.set_uniform("normal", texture);
+        assert_eq!(sampler_units(&recorder, &program), [0, 1]);
+    }
+
+    #[test]
+    fn explicit_units_move_assigned_samplers() {
+        let (recorder, program, texture) = two_samplers();
+        recorder.clear();
+        for _ in 0..2 {
+            program.set_uniform("normal", texture.at_unit(0));
+            program.set_uniform("diffuse", texture.clone());
+        }
+        assert_eq!(sampler_units(&recorder, &program), [0, 2, 0, 2]);
+    }
+
+    #[test]
+    fn explicit_unit_of_a_bound_sampler_is_an_error() {
+        let (_, program, texture) = two_samplers();
+        program.set_uniform("diffuse", texture.clone());
+        assert!(matches!(
+            program.try_set_uniform("normal", texture.at_unit(0)),
+            Err(Error::TextureUnitTaken(0))
+        ));
+        // explicit units of the same sampler can change
+        program.set_uniform("normal", texture.at_unit(3));
+        program.set_uniform("normal", texture.at_unit(4));
+        assert!(program
+            .try_set_uniform("normal", texture.at_unit(3))
+            .is_ok());
+    }
+
+    #[test]
+    fn running_out_of_texture_units_is_an_error() {
+        let (recorder, _, texture) = two_samplers();
+        recorder.set_parameter(glow::MAX_COMBINED_TEXTURE_IMAGE_UNITS, 2);
+        let gl = GL::from_backend(recorder.clone());
+        let shader = Shader::from_frag_source(&gl, "").unwrap();
+        let program = Program::from_shaders(&gl, &[shader]).unwrap();
+        // the assigned sampler on unit 0 has nowhere to move
+        assert!(matches!(
+            program.try_set_uniform("normal", texture.at_unit(0)),
+            Err(Error::NoFreeTextureUnit(2))
+        ));
+        // nor has a sampler the reflection missed
+        assert!(matches!(
+            texture.set_for_program(&program, 99),
+            Err(Error::NoFreeTextureUnit(2))
+        ));
+    }
+
+    #[test]
+    fn link_failure_returns_the_log_and_deletes_the_program() {
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        let shader = Shader::from_vert_source(&gl, "").unwrap();
+        recorder.script_link(false, "error: no main\n");
+        match Program::from_shaders(&gl, &[shader]) {
+            Err(Error::Link(log)) => assert_eq!(log, "error: no main\n"),
+            other => panic!("unexpected {:?}", other.map(|p| p.id())),
+        }
+        assert!(recorder
+            .events()
+            .iter()
+            .any(|event| matches!(event, Event::DeleteProgram { .. })));
+    }
+}
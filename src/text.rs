@@ -0,0 +1,732 @@
+// Text from AngelCode BMFont fonts (the text .fnt format). Glyphs are
+// copied from the font pages into a TextureAtlas the first time a string
+// uses them, and strings are laid out into one instanced quad per glyph.
+// Positions are in pixels with y pointing down, (0, 0) the top left corner
+// of the screen.
+use super::atlas::{TextureAtlas, UvRect};
+use super::data::{f32_f32, f32_f32_f32_f32};
+use super::shader::{Program, Shader};
+use super::texture::{ImageOptions, Texture};
+use super::{
+    Blend, DrawParams, DrawType, Error, Frame, Primitive, VertexAttribPointers,
+    GL,
+};
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    pub page: usize,
+    // (x, y, width, height) in the page image
+    pub rect: (u32, u32, u32, u32),
+    // from the pen position to the top left corner of the glyph
+    pub offset: (i32, i32),
+    pub advance: i32,
+}
+
+#[derive(Debug)]
+pub struct BitmapFont {
+    line_height: u32,
+    base: u32,
+    glyphs: HashMap<char, Glyph>,
+    kernings: HashMap<(char, char), i32>,
+    pages: Vec<RgbaImage>,
+}
+
+impl BitmapFont {
+    // Page images are looked up next to the .fnt file
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<BitmapFont, Error> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| Error::Io {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        BitmapFont::parse(&source, |file| {
+            Ok(ImageOptions::default().load_path(dir.join(file))?.image)
+        })
+    }
+
+    // `load_page` gets the file name of each page as written in `source`
+    pub fn parse<F>(source: &str, mut load_page: F) -> Result<BitmapFont, Error>
+    where
+        F: FnMut(&str) -> Result<RgbaImage, Error>,
+    {
+        let mut font = BitmapFont {
+            line_height: 0,
+            base: 0,
+            glyphs: HashMap::new(),
+            kernings: HashMap::new(),
+            pages: Vec::new(),
+        };
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index as u32 + 1;
+            let error = |reason: String| Error::Font {
+                line: line_number,
+                reason,
+            };
+            let (tag, fields) = split_fields(line);
+            let field = |key: &str| -> Result<i64, Error> {
+                let value = fields
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, value)| *value)
+                    .ok_or_else(|| error(format!("{} has no {}", tag, key)))?;
+                value.parse().map_err(|_| {
+                    error(format!("{} {} is not a number: {}", tag, key, value))
+                })
+            };
+            let out_of_range = |key: &str, value: i64| {
+                error(format!("{} {} is out of range: {}", tag, key, value))
+            };
+            let unsigned = |key: &str| -> Result<u32, Error> {
+                let value = field(key)?;
+                u32::try_from(value).map_err(|_| out_of_range(key, value))
+            };
+            let signed = |key: &str| -> Result<i32, Error> {
+                let value = field(key)?;
+                i32::try_from(value).map_err(|_| out_of_range(key, value))
+            };
+            let character = |key: &str| -> Result<char, Error> {
+                let id = unsigned(key)?;
+                std::char::from_u32(id)
+                    .ok_or_else(|| error(format!("{} is not a character", id)))
+            };
+            match tag {
+                "common" => {
+                    font.line_height = unsigned("lineHeight")?;
+                    font.base = unsigned("base")?;
+                }
+                "page" => {
+                    let id = unsigned("id")? as usize;
+                    if id != font.pages.len() {
+                        return Err(error(format!(
+                            "page {} is out of order",
+                            id
+                        )));
+                    }
+                    let file = fields
+                        .iter()
+                        .find(|(key, _)| *key == "file")
+                        .map(|(_, file)| *file)
+                        .ok_or_else(|| error("page has no file".to_string()))?;
+                    font.pages.push(load_page(file)?);
+                }
+                "char" => {
+                    let glyph = Glyph {
+                        page: unsigned("page")? as usize,
+                        rect: (
+                            unsigned("x")?,
+                            unsigned("y")?,
+                            unsigned("width")?,
+                            unsigned("height")?,
+                        ),
+                        offset: (signed("xoffset")?, signed("yoffset")?),
+                        advance: signed("xadvance")?,
+                    };
+                    let (x, y, width, height) = glyph.rect;
+                    let within = |start: u32, len: u32, size: u32| {
+                        start.checked_add(len).is_some_and(|end| end <= size)
+                    };
+                    let inside =
+                        font.pages.get(glyph.page).is_some_and(|page| {
+                            within(x, width, page.width())
+                                && within(y, height, page.height())
+                        });
+                    if !inside {
+                        return Err(error(format!(
+                            "glyph is outside of page {}",
+                            glyph.page
+                        )));
+                    }
+                    font.glyphs.insert(character("id")?, glyph);
+                }
+                "kerning" => {
+                    font.kernings.insert(
+                        (character("first")?, character("second")?),
+                        signed("amount")?,
+                    );
+                }
+                _ => (),
+            }
+        }
+        Ok(font)
+    }
+
+    pub fn line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    // distance from the top of a line to the baseline
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c)
+    }
+
+    // added to the advance of `first` when `second` follows it
+    pub fn kerning(&self, first: char, second: char) -> i32 {
+        self.kernings.get(&(first, second)).copied().unwrap_or(0)
+    }
+
+    pub fn pages(&self) -> &[RgbaImage] {
+        &self.pages
+    }
+
+    // Width of a single line, characters without a glyph are skipped
+    pub fn measure_line(&self, line: &str, scale: f32) -> f32 {
+        let mut width = 0;
+        let mut previous = None;
+        for c in line.chars() {
+            let glyph = match self.glyph(c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            if let Some(previous) = previous {
+                width += self.kerning(previous, c);
+            }
+            width += glyph.advance;
+            previous = Some(c);
+        }
+        width as f32 * scale
+    }
+
+    // Splits at '\n' and, with `max_width`, between words. A word longer
+    // than `max_width` gets a line of its own. Wrapped lines don't keep the
+    // spaces they were broken at.
+    pub fn break_lines<'a>(
+        &self,
+        text: &'a str,
+        scale: f32,
+        max_width: Option<f32>,
+    ) -> Vec<&'a str> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let max_width = match max_width {
+                Some(max_width) => max_width,
+                None => {
+                    lines.push(paragraph);
+                    continue;
+                }
+            };
+            let fits = |line: &str| self.measure_line(line, scale) <= max_width;
+            let mut wrapped = Vec::new();
+            let mut start = 0;
+            // end of the last word that fits on the current line
+            let mut fit = None;
+            let word_ends = paragraph
+                .char_indices()
+                .filter(|(_, c)| *c == ' ')
+                .map(|(i, _)| i)
+                .chain(std::iter::once(paragraph.len()));
+            for end in word_ends {
+                if fits(&paragraph[start..end]) {
+                    fit = Some(end);
+                    continue;
+                }
+                if let Some(fit) = fit {
+                    wrapped.push(&paragraph[start..fit]);
+                    start = fit + 1;
+                }
+                if fit.is_some() && fits(&paragraph[start..end]) {
+                    fit = Some(end);
+                } else {
+                    wrapped.push(&paragraph[start..end]);
+                    start = end + 1;
+                    fit = None;
+                }
+            }
+            if let Some(fit) = fit {
+                wrapped.push(&paragraph[start..fit]);
+            }
+            let first = lines.len();
+            for (index, line) in wrapped.into_iter().enumerate() {
+                let line = line.trim_end_matches(' ');
+                let line = match index {
+                    0 => line,
+                    _ => line.trim_start_matches(' '),
+                };
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+            }
+            // an empty paragraph is still a line
+            if lines.len() == first {
+                lines.push("");
+            }
+        }
+        lines
+    }
+}
+
+// `tag key=value key="quoted value"` into the tag and its fields
+fn split_fields(line: &str) -> (&str, Vec<(&str, &str)>) {
+    let line = line.trim();
+    let (tag, mut rest) = match line.find(' ') {
+        Some(space) => (&line[..space], &line[space..]),
+        None => (line, ""),
+    };
+    let mut fields = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let equals = match rest.find('=') {
+            Some(equals) => equals,
+            None => break,
+        };
+        let key = rest[..equals].trim();
+        rest = &rest[equals + 1..];
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            rest = quoted.get(end + 1..).unwrap_or("");
+            &quoted[..end]
+        } else {
+            let end = rest.find(' ').unwrap_or(rest.len());
+            let value = &rest[..end];
+            rest = &rest[end..];
+            value
+        };
+        fields.push((key, value));
+    }
+    (tag, fields)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Align {
+    // lines start at the x of the text position
+    #[default]
+    Left,
+    // lines are centered on it
+    Center,
+    // lines end at it
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub color: (f32, f32, f32, f32),
+    // 1.0 draws glyphs at the size they have in the font pages
+    pub scale: f32,
+    pub align: Align,
+    // wraps lines between words when set
+    pub max_width: Option<f32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            color: (1.0, 1.0, 1.0, 1.0),
+            scale: 1.0,
+            align: Align::default(),
+            max_width: None,
+        }
+    }
+}
+
+// One quad of TextRenderer, drawn per instance
+#[derive(VertexAttribPointers, Copy, Clone)]
+#[repr(C, packed)]
+pub struct GlyphInstance {
+    // x, y, width, height in pixels
+    #[divisor = "1"]
+    pub rect: f32_f32_f32_f32,
+    // min u, min v, max u, max v
+    #[divisor = "1"]
+    pub uv: f32_f32_f32_f32,
+    #[divisor = "1"]
+    pub color: f32_f32_f32_f32,
+}
+
+// Glyph instances on one page of the glyph atlas
+pub struct GlyphBatch {
+    pub page: usize,
+    pub instances: Vec<GlyphInstance>,
+}
+
+pub struct GlyphCache {
+    font: BitmapFont,
+    atlas: TextureAtlas<char>,
+}
+
+impl GlyphCache {
+    pub fn new(font: BitmapFont, page_size: (u32, u32)) -> GlyphCache {
+        GlyphCache {
+            font,
+            atlas: TextureAtlas::new(page_size, 1),
+        }
+    }
+
+    pub fn font(&self) -> &BitmapFont {
+        &self.font
+    }
+
+    pub fn atlas(&self) -> &TextureAtlas<char> {
+        &self.atlas
+    }
+
+    // `position` is the top left corner of the first line for Align::Left,
+    // the top middle for Center and the top right for Right
+    pub fn layout(
+        &mut self,
+        gl: &GL,
+        text: &str,
+        (x, y): (f32, f32),
+        style: &TextStyle,
+    ) -> Result<Vec<GlyphBatch>, Error> {
+        let scale = style.scale;
+        let mut batches: Vec<GlyphBatch> = Vec::new();
+        let lines = self.font.break_lines(text, scale, style.max_width);
+        for (row, line) in lines.into_iter().enumerate() {
+            let width = self.font.measure_line(line, scale);
+            let mut pen = match style.align {
+                Align::Left => x,
+                Align::Center => x - width / 2.0,
+                Align::Right => x - width,
+            };
+            let top = y + (row as u32 * self.font.line_height) as f32 * scale;
+            let mut previous = None;
+            for c in line.chars() {
+                let glyph = match self.font.glyph(c) {
+                    Some(glyph) => *glyph,
+                    None => continue,
+                };
+                if let Some(previous) = previous {
+                    pen += self.font.kerning(previous, c) as f32 * scale;
+                }
+                previous = Some(c);
+                let (_, _, width, height) = glyph.rect;
+                if width > 0 && height > 0 {
+                    let (page, uv) = self.cached(gl, c, &glyph)?;
+                    let instance = GlyphInstance {
+                        rect: (
+                            pen + glyph.offset.0 as f32 * scale,
+                            top + glyph.offset.1 as f32 * scale,
+                            width as f32 * scale,
+                            height as f32 * scale,
+                        )
+                            .into(),
+                        uv: (uv.min.0, uv.min.1, uv.max.0, uv.max.1).into(),
+                        color: style.color.into(),
+                    };
+                    match batches.iter_mut().find(|b| b.page == page) {
+                        Some(batch) => batch.instances.push(instance),
+                        None => batches.push(GlyphBatch {
+                            page,
+                            instances: vec![instance],
+                        }),
+                    }
+                }
+                pen += glyph.advance as f32 * scale;
+            }
+        }
+        Ok(batches)
+    }
+
+    // (atlas page, uv rect) of `c`, added to the atlas on first use
+    fn cached(
+        &mut self,
+        gl: &GL,
+        c: char,
+        glyph: &Glyph,
+    ) -> Result<(usize, UvRect), Error> {
+        let sprite = match self.atlas.get(&c) {
+            Some(sprite) => sprite,
+            None => {
+                let page = &self.font.pages[glyph.page];
+                let (x, y, width, height) = glyph.rect;
+                let image = RgbaImage::from_fn(width, height, |i, j| {
+                    *page.get_pixel(x + i, y + j)
+                });
+                self.atlas.add(gl, c, &image)?
+            }
+        };
+        Ok((sprite.page, sprite.uv))
+    }
+}
+
+#[derive(VertexAttribPointers, Copy, Clone)]
+#[repr(C, packed)]
+pub struct GlyphCorner {
+    corner: f32_f32,
+}
+
+const VERTEX_SHADER: &str = "
+in vec2 corner;
+in vec4 rect;
+in vec4 uv;
+in vec4 color;
+uniform vec2 screen;
+out vec2 v_uv;
+out vec4 v_color;
+void main() {
+    vec2 pixel = rect.xy + corner * rect.zw;
+    v_uv = mix(uv.xy, uv.zw, corner);
+    v_color = color;
+    gl_Position = vec4(
+        pixel.x / screen.x * 2.0 - 1.0,
+        1.0 - pixel.y / screen.y * 2.0,
+        0.0,
+        1.0
+    );
+}
+";
+
+const FRAGMENT_SHADER: &str = "
+in vec2 v_uv;
+in vec4 v_color;
+uniform sampler2D glyphs;
+out vec4 frag_color;
+void main() {
+    frag_color = v_color * texture(glyphs, v_uv);
+}
+";
+
+// Draws GlyphCache::layout output with alpha blending. Needs instancing,
+// so GL 3.3 or GLES 3.
+pub struct TextRenderer {
+    program: Program,
+    quad: GlyphCornerBuffer<GlyphCorner>,
+    instances: GlyphInstanceBuffer<GlyphInstance>,
+}
+
+impl TextRenderer {
+    pub fn new(gl: &GL) -> Result<TextRenderer, Error> {
+        let vertex = Shader::from_portable_source(
+            gl,
+            VERTEX_SHADER,
+            glow::VERTEX_SHADER,
+        )?;
+        let fragment = Shader::from_portable_source(
+            gl,
+            FRAGMENT_SHADER,
+            glow::FRAGMENT_SHADER,
+        )?;
+        let program = Program::from_shaders(gl, &[vertex, fragment])?;
+        let corners: Vec<GlyphCorner> =
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+                .iter()
+                .map(|&corner| GlyphCorner {
+                    corner: corner.into(),
+                })
+                .collect();
+        let quad = GlyphCornerBuffer::new(gl, &corners)?;
+        let instances = GlyphInstanceBuffer::empty_new(gl)?;
+        program.set_layout(gl, &quad.vao, &[&quad, &instances])?;
+        Ok(TextRenderer {
+            program,
+            quad,
+            instances,
+        })
+    }
+
+    // `screen` is the viewport size in pixels
+    pub fn draw(
+        &mut self,
+        frame: &Frame,
+        cache: &GlyphCache,
+        batches: &[GlyphBatch],
+        screen: (f32, f32),
+    ) -> Result<(), Error> {
+        self.program.set_uniform("screen", screen);
+        for batch in batches.iter().filter(|b| !b.instances.is_empty()) {
+            let page: &Texture = match cache.atlas().pages().nth(batch.page) {
+                Some(page) => page,
+                None => continue,
+            };
+            self.instances.dynamic_draw_data(&batch.instances);
+            self.program.set_uniform("glyphs", page.clone());
+            frame.draw(
+                &self.quad.vao,
+                None,
+                &self.program,
+                &DrawParams {
+                    draw_type: DrawType::Instancing(batch.instances.len()),
+                    primitive: Primitive::TriangleStrip,
+                    blend: Some(Blend::alpha()),
+                    ..Default::default()
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+
+    // 'a' and 'b' advance 5 pixels, 'b' moves 1 closer after 'a', space
+    // advances 3
+    const FONT: &str = "info face=\"Test Font\" size=8
+common lineHeight=10 base=8 pages=1
+page id=0 file=\"test font.png\"
+char id=97 x=0 y=0 width=4 height=6 xoffset=1 yoffset=2 xadvance=5 page=0 chnl=15
+char id=98 x=4 y=0 width=4 height=6 xoffset=0 yoffset=2 xadvance=5 page=0 chnl=15
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=3 page=0 chnl=15
+kerning first=97 second=98 amount=-1
+";
+
+    fn font() -> BitmapFont {
+        BitmapFont::parse(FONT, |file| {
+            assert_eq!(file, "test font.png");
+            Ok(RgbaImage::new(16, 16))
+        })
+        .unwrap()
+    }
+
+    fn font_error(line: &str) -> (u32, String) {
+        let source = format!("{}{}\n", FONT, line);
+        match BitmapFont::parse(&source, |_| Ok(RgbaImage::new(16, 16))) {
+            Err(Error::Font { line, reason }) => (line, reason),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn split_fields_reads_quoted_values() {
+        assert_eq!(
+            split_fields("page id=0 file=\"my font.png\"  x=-1"),
+            (
+                "page",
+                vec![("id", "0"), ("file", "my font.png"), ("x", "-1")]
+            )
+        );
+        assert_eq!(split_fields("chars"), ("chars", vec![]));
+        assert_eq!(
+            split_fields("info face=\"\""),
+            ("info", vec![("face", "")])
+        );
+    }
+
+    #[test]
+    fn parse_reads_glyphs_and_kerning() {
+        let font = font();
+        assert_eq!(font.line_height(), 10);
+        assert_eq!(font.base(), 8);
+        assert_eq!(font.pages().len(), 1);
+        assert_eq!(
+            font.glyph('b'),
+            Some(&Glyph {
+                page: 0,
+                rect: (4, 0, 4, 6),
+                offset: (0, 2),
+                advance: 5,
+            })
+        );
+        assert_eq!(font.glyph('c'), None);
+        assert_eq!(font.kerning('a', 'b'), -1);
+        assert_eq!(font.kerning('b', 'a'), 0);
+    }
+
+    #[test]
+    fn parse_rejects_negative_and_overflowing_fields() {
+        let (line, reason) = font_error(
+            "char id=99 x=0 y=0 width=-4 height=6 xoffset=0 yoffset=0 \
+             xadvance=5 page=0",
+        );
+        assert_eq!(line, 8);
+        assert!(reason.contains("width"), "{}", reason);
+        let (_, reason) = font_error(
+            "char id=99 x=4294967295 y=0 width=2 height=6 xoffset=0 \
+             yoffset=0 xadvance=5 page=0",
+        );
+        assert!(reason.contains("outside of page 0"), "{}", reason);
+        let (_, reason) = font_error("kerning first=-97 second=98 amount=1");
+        assert!(reason.contains("first"), "{}", reason);
+        let (_, reason) = font_error("page id=x file=\"a.png\"");
+        assert!(reason.contains("not a number"), "{}", reason);
+    }
+
+    #[test]
+    fn measure_line_applies_kerning_and_scale() {
+        let font = font();
+        assert_eq!(font.measure_line("ab", 1.0), 9.0);
+        assert_eq!(font.measure_line("ba", 1.0), 10.0);
+        assert_eq!(font.measure_line("a b", 2.0), 26.0);
+        // characters without a glyph don't break kerning pairs
+        assert_eq!(font.measure_line("a?b", 1.0), 9.0);
+        assert_eq!(font.measure_line("", 1.0), 0.0);
+    }
+
+    #[test]
+    fn break_lines_wraps_between_words() {
+        let font = font();
+        assert_eq!(
+            font.break_lines("ab\n\nab ab", 1.0, None),
+            vec!["ab", "", "ab ab"]
+        );
+        assert_eq!(
+            font.break_lines("ab ab ab", 1.0, Some(21.0)),
+            vec!["ab ab", "ab"]
+        );
+        assert_eq!(
+            font.break_lines("ab\nab ab", 1.0, Some(10.0)),
+            vec!["ab", "ab", "ab"]
+        );
+        // twice the size no longer fits two words
+        assert_eq!(
+            font.break_lines("ab ab", 2.0, Some(21.0)),
+            vec!["ab", "ab"]
+        );
+    }
+
+    #[test]
+    fn break_lines_gives_long_words_their_own_line() {
+        let font = font();
+        assert_eq!(
+            font.break_lines("a ababab a", 1.0, Some(10.0)),
+            vec!["a", "ababab", "a"]
+        );
+        assert_eq!(font.break_lines("ababab", 1.0, Some(10.0)), vec!["ababab"]);
+    }
+
+    #[test]
+    fn break_lines_drops_spaces_at_breaks() {
+        let font = font();
+        assert_eq!(font.break_lines("ab ", 1.0, Some(10.0)), vec!["ab"]);
+        assert_eq!(
+            font.break_lines("ab  ab ", 1.0, Some(10.0)),
+            vec!["ab", "ab"]
+        );
+        assert_eq!(font.break_lines("\n", 1.0, Some(10.0)), vec!["", ""]);
+    }
+
+    #[test]
+    fn layout_offsets_lines_by_alignment() {
+        let gl = GL::from_backend(Recorder::new());
+        let mut cache = GlyphCache::new(font(), (64, 64));
+        let mut positions = |align| {
+            let style = TextStyle {
+                align,
+                ..Default::default()
+            };
+            let batches =
+                cache.layout(&gl, "ab\nb", (100.0, 20.0), &style).unwrap();
+            batches[0]
+                .instances
+                .iter()
+                .map(|instance| {
+                    let rect = instance.rect;
+                    (rect.0, rect.1)
+                })
+                .collect::<Vec<_>>()
+        };
+        // 'a' has an x offset of 1, the second line is 5 wide
+        assert_eq!(
+            positions(Align::Left),
+            vec![(101.0, 22.0), (104.0, 22.0), (100.0, 32.0)]
+        );
+        assert_eq!(
+            positions(Align::Center),
+            vec![(96.5, 22.0), (99.5, 22.0), (97.5, 32.0)]
+        );
+        assert_eq!(
+            positions(Align::Right),
+            vec![(92.0, 22.0), (95.0, 22.0), (95.0, 32.0)]
+        );
+    }
+}
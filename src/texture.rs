@@ -0,0 +1,783 @@
+use super::shader::GlslVersion;
+use super::{Error, GL};
+use image::{DynamicImage, ImageError, RgbaImage};
+use std::os::raw::c_uint;
+use std::path::Path;
+use std::rc::Rc;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TextureFormat {
+    R8,
+    RG8,
+    RGB8,
+    #[default]
+    RGBA8,
+    SRGB8A8,
+    // float formats are uploaded from f32 data
+    R16F,
+    RGBA16F,
+    RGBA32F,
+    Depth24,
+    Depth32F,
+    Depth24Stencil8,
+}
+
+impl TextureFormat {
+    // (internal format, upload format, upload type, bytes per pixel)
+    fn info(self) -> (u32, u32, u32, usize) {
+        match self {
+            TextureFormat::R8 => (glow::R8, glow::RED, glow::UNSIGNED_BYTE, 1),
+            TextureFormat::RG8 => (glow::RG8, glow::RG, glow::UNSIGNED_BYTE, 2),
+            TextureFormat::RGB8 => {
+                (glow::RGB8, glow::RGB, glow::UNSIGNED_BYTE, 3)
+            }
+            TextureFormat::RGBA8 => {
+                (glow::RGBA8, glow::RGBA, glow::UNSIGNED_BYTE, 4)
+            }
+            TextureFormat::SRGB8A8 => {
+                (glow::SRGB8_ALPHA8, glow::RGBA, glow::UNSIGNED_BYTE, 4)
+            }
+            TextureFormat::R16F => (glow::R16F, glow::RED, glow::FLOAT, 4),
+            TextureFormat::RGBA16F => {
+                (glow::RGBA16F, glow::RGBA, glow::FLOAT, 16)
+            }
+            TextureFormat::RGBA32F => {
+                (glow::RGBA32F, glow::RGBA, glow::FLOAT, 16)
+            }
+            TextureFormat::Depth24 => (
+                glow::DEPTH_COMPONENT24,
+                glow::DEPTH_COMPONENT,
+                glow::UNSIGNED_INT,
+                4,
+            ),
+            TextureFormat::Depth32F => (
+                glow::DEPTH_COMPONENT32F,
+                glow::DEPTH_COMPONENT,
+                glow::FLOAT,
+                4,
+            ),
+            TextureFormat::Depth24Stencil8 => (
+                glow::DEPTH24_STENCIL8,
+                glow::DEPTH_STENCIL,
+                glow::UNSIGNED_INT_24_8,
+                4,
+            ),
+        }
+    }
+
+    pub fn internal_format(self) -> u32 {
+        self.info().0
+    }
+
+    pub fn format(self) -> u32 {
+        self.info().1
+    }
+
+    pub fn data_type(self) -> u32 {
+        self.info().2
+    }
+
+    // size of one pixel of upload data
+    pub fn bytes_per_pixel(self) -> usize {
+        self.info().3
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    #[default]
+    ClampToEdge,
+}
+
+impl Wrap {
+    fn wrap(self) -> u32 {
+        match self {
+            Wrap::Repeat => glow::REPEAT,
+            Wrap::MirroredRepeat => glow::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => glow::CLAMP_TO_EDGE,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Filter {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+impl Filter {
+    fn filter(self) -> u32 {
+        match self {
+            Filter::Nearest => glow::NEAREST,
+            Filter::Linear => glow::LINEAR,
+        }
+    }
+
+    // `self` within a level, `mipmap` between levels
+    fn min_filter(self, mipmap: Option<Filter>) -> u32 {
+        match (self, mipmap) {
+            (filter, None) => filter.filter(),
+            (Filter::Nearest, Some(Filter::Nearest)) => {
+                glow::NEAREST_MIPMAP_NEAREST
+            }
+            (Filter::Nearest, Some(Filter::Linear)) => {
+                glow::NEAREST_MIPMAP_LINEAR
+            }
+            (Filter::Linear, Some(Filter::Nearest)) => {
+                glow::LINEAR_MIPMAP_NEAREST
+            }
+            (Filter::Linear, Some(Filter::Linear)) => {
+                glow::LINEAR_MIPMAP_LINEAR
+            }
+        }
+    }
+}
+
+// Deletes the GL texture when the last Texture clone is dropped
+#[derive(Debug)]
+struct TextureHandle {
+    gl: GL,
+    texture: c_uint,
+}
+
+impl Drop for TextureHandle {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_texture(self.texture);
+        }
+    }
+}
+
+// Clones share one GL texture
+#[derive(Clone, Debug)]
+pub struct Texture {
+    pub texture: c_uint,
+    w: u32,
+    h: u32,
+    format: TextureFormat,
+    mipmapped: bool,
+    handle: Rc<TextureHandle>,
+}
+
+impl PartialEq for Texture {
+    fn eq(&self, other: &Texture) -> bool {
+        self.texture == other.texture
+    }
+}
+
+impl Texture {
+    pub fn from_rgba8(
+        gl_ctx: &GL,
+        width: u32,
+        height: u32,
+        bytes: &[u8],
+    ) -> Result<Texture, Error> {
+        TextureBuilder::new(width, height).build(gl_ctx, Some(bytes))
+    }
+
+    // Decodes any format the image crate knows into an RGBA8 texture
+    pub fn from_path<P: AsRef<Path>>(
+        gl: &GL,
+        path: P,
+        options: ImageOptions,
+    ) -> Result<Texture, Error> {
+        options.load_path(path)?.build(gl)
+    }
+
+    pub fn from_memory(
+        gl: &GL,
+        bytes: &[u8],
+        options: ImageOptions,
+    ) -> Result<Texture, Error> {
+        options.load_memory(bytes)?.build(gl)
+    }
+
+    // Sets a sampler to an explicit unit instead of the one the program
+    // assigned, see Program::texture_unit
+    pub fn at_unit(&self, unit: u32) -> TextureUnit {
+        TextureUnit {
+            texture: self.clone(),
+            unit,
+        }
+    }
+
+    pub fn with_sampler(&self, sampler: &Sampler) -> SampledTexture {
+        SampledTexture {
+            texture: self.clone(),
+            sampler: sampler.clone(),
+            unit: None,
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.w, self.h)
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    // `bytes` holds the rows of the region bottom to top, in the layout
+    // TextureBuilder::build takes. Mipmaps are regenerated after the write.
+    pub fn write_region(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        bytes: &[u8],
+    ) -> Result<(), Error> {
+        self.check_region((x, y, width, height))?;
+        let gl = &self.handle.gl;
+        let (_, format, data_type, bpp) = self.format.info();
+        let expected = width as usize * height as usize * bpp;
+        if bytes.len() != expected {
+            return Err(Error::DataSize {
+                expected,
+                found: bytes.len(),
+            });
+        }
+        unsafe {
+            let restore = Bound::texture(gl, self.texture);
+            gl.tex_sub_image_2d_u8_slice(
+                glow::TEXTURE_2D,
+                0,
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                format,
+                data_type,
+                Some(bytes),
+            );
+            if self.mipmapped {
+                gl.generate_mipmap(glow::TEXTURE_2D);
+            }
+            restore.restore(gl);
+        }
+        Ok(())
+    }
+
+    // Reads level 0 through a temporary framebuffer. Rows come back in
+    // texture order, bottom row first, so an upload reads back unchanged.
+    // Missing channels read as 0 and alpha as 255.
+    pub fn read_pixels(&self) -> Result<RgbaImage, Error> {
+        let gl = &self.handle.gl;
+        match self.format {
+            TextureFormat::R8
+            | TextureFormat::RG8
+            | TextureFormat::RGB8
+            | TextureFormat::RGBA8
+            | TextureFormat::SRGB8A8 => (),
+            format => {
+                return Err(Error::Unsupported(format!(
+                    "reading back {:?} textures",
+                    format
+                )))
+            }
+        }
+        // GLES 2 has no separate read framebuffer
+        let (target, binding) = if gl.glsl_version() == GlslVersion::Es100 {
+            (glow::FRAMEBUFFER, glow::FRAMEBUFFER_BINDING)
+        } else {
+            (glow::READ_FRAMEBUFFER, glow::READ_FRAMEBUFFER_BINDING)
+        };
+        let mut pixels = vec![0; self.w as usize * self.h as usize * 4];
+        unsafe {
+            let previous = object(gl.get_parameter_i32(binding));
+            let alignment = gl.get_parameter_i32(glow::PACK_ALIGNMENT);
+            let framebuffer =
+                gl.create_framebuffer().map_err(Error::ObjectCreation)?;
+            gl.bind_framebuffer(target, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                target,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(self.texture),
+                0,
+            );
+            let status = gl.check_framebuffer_status(target);
+            let result = if status != glow::FRAMEBUFFER_COMPLETE {
+                Err(Error::Unsupported(format!(
+                    "framebuffer for {:?} texture is incomplete: {:#x}",
+                    self.format, status
+                )))
+            } else {
+                gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+                gl.read_pixels(
+                    0,
+                    0,
+                    self.w as i32,
+                    self.h as i32,
+                    glow::RGBA,
+                    glow::UNSIGNED_BYTE,
+                    &mut pixels,
+                )
+                .map_err(Error::Unsupported)
+            };
+            gl.pixel_store_i32(glow::PACK_ALIGNMENT, alignment);
+            gl.bind_framebuffer(target, previous);
+            gl.delete_framebuffer(framebuffer);
+            result?;
+        }
+        Ok(RgbaImage::from_raw(self.w, self.h, pixels)
+            .expect("pixels are sized for the texture"))
+    }
+
+    fn check_region(
+        &self,
+        (x, y, width, height): (u32, u32, u32, u32),
+    ) -> Result<(), Error> {
+        let fits = |start: u32, len: u32, max: u32| {
+            start.checked_add(len).is_some_and(|end| end <= max)
+        };
+        if fits(x, width, self.w) && fits(y, height, self.h) {
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds {
+                region: (x, y, width, height),
+                size: (self.w, self.h),
+            })
+        }
+    }
+
+    pub fn new(
+        gl_ctx: &GL,
+        (width, height): (u32, u32),
+    ) -> Result<Texture, Error> {
+        TextureBuilder::new(width, height)
+            .min_filter(Filter::Linear)
+            .build(gl_ctx, None)
+    }
+}
+
+// Units the program would assign to other samplers move out of the way,
+// setting a unit a texture was already bound to for another sampler is an
+// error
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureUnit {
+    pub texture: Texture,
+    pub unit: u32,
+}
+
+#[derive(Debug)]
+struct SamplerHandle {
+    gl: GL,
+    sampler: c_uint,
+}
+
+impl Drop for SamplerHandle {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_sampler(self.sampler);
+        }
+    }
+}
+
+// Sampling state paired with a texture when it is bound, see
+// Texture::with_sampler. Contexts without sampler objects (GLES 2, WebGL 1)
+// get the parameters set on the texture itself at every bind instead.
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    sampling: Sampling,
+    handle: Option<Rc<SamplerHandle>>,
+}
+
+impl PartialEq for Sampler {
+    fn eq(&self, other: &Sampler) -> bool {
+        self.sampling == other.sampling && self.id() == other.id()
+    }
+}
+
+impl Sampler {
+    pub fn new(gl: &GL, sampling: Sampling) -> Result<Sampler, Error> {
+        if !gl.supports_samplers() {
+            return Ok(Sampler {
+                sampling,
+                handle: None,
+            });
+        }
+        unsafe {
+            let sampler = gl.create_sampler().map_err(Error::ObjectCreation)?;
+            for (parameter, value) in sampling.parameters().iter() {
+                gl.sampler_parameter_i32(sampler, *parameter, *value);
+            }
+            Ok(Sampler {
+                sampling,
+                handle: Some(Rc::new(SamplerHandle {
+                    gl: gl.clone(),
+                    sampler,
+                })),
+            })
+        }
+    }
+
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    // None on the texture parameter fallback
+    pub fn id(&self) -> Option<c_uint> {
+        self.handle.as_ref().map(|handle| handle.sampler)
+    }
+
+    // expects the texture already bound to `unit`, which is active
+    pub(crate) unsafe fn bind(&self, gl: &GL, unit: u32) {
+        match self.id() {
+            Some(sampler) => gl.bind_sampler(unit, Some(sampler)),
+            None => self.sampling.apply_to_texture(gl),
+        }
+    }
+}
+
+// A texture sampled through a Sampler, on the unit the program assigned
+// unless `unit` is set
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampledTexture {
+    pub texture: Texture,
+    pub sampler: Sampler,
+    pub unit: Option<u32>,
+}
+
+impl SampledTexture {
+    pub fn at_unit(mut self, unit: u32) -> SampledTexture {
+        self.unit = Some(unit);
+        self
+    }
+}
+
+// Wrap and filter state, baked into a texture by TextureBuilder or kept
+// apart in a Sampler
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Sampling {
+    pub wrap_s: Wrap,
+    pub wrap_t: Wrap,
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    // filter between mip levels, None samples level 0 only
+    pub mipmap_filter: Option<Filter>,
+}
+
+impl Sampling {
+    pub fn new(filter: Filter, wrap: Wrap) -> Sampling {
+        Sampling {
+            wrap_s: wrap,
+            wrap_t: wrap,
+            min_filter: filter,
+            mag_filter: filter,
+            mipmap_filter: None,
+        }
+    }
+
+    // (parameter, value) pairs for tex_parameter_i32 and
+    // sampler_parameter_i32
+    fn parameters(&self) -> [(u32, i32); 4] {
+        [
+            (glow::TEXTURE_WRAP_S, self.wrap_s.wrap() as i32),
+            (glow::TEXTURE_WRAP_T, self.wrap_t.wrap() as i32),
+            (glow::TEXTURE_MAG_FILTER, self.mag_filter.filter() as i32),
+            (
+                glow::TEXTURE_MIN_FILTER,
+                self.min_filter.min_filter(self.mipmap_filter) as i32,
+            ),
+        ]
+    }
+
+    // sets the texture bound to TEXTURE_2D of the active unit
+    unsafe fn apply_to_texture(&self, gl: &GL) {
+        for (parameter, value) in self.parameters().iter() {
+            gl.tex_parameter_i32(glow::TEXTURE_2D, *parameter, *value);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureBuilder {
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    sampling: Sampling,
+}
+
+impl TextureBuilder {
+    pub fn new(width: u32, height: u32) -> TextureBuilder {
+        TextureBuilder {
+            width,
+            height,
+            format: TextureFormat::default(),
+            sampling: Sampling::default(),
+        }
+    }
+
+    pub fn format(mut self, format: TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn wrap(self, wrap: Wrap) -> Self {
+        self.wrap_s(wrap).wrap_t(wrap)
+    }
+
+    pub fn wrap_s(mut self, wrap: Wrap) -> Self {
+        self.sampling.wrap_s = wrap;
+        self
+    }
+
+    pub fn wrap_t(mut self, wrap: Wrap) -> Self {
+        self.sampling.wrap_t = wrap;
+        self
+    }
+
+    pub fn filter(self, filter: Filter) -> Self {
+        self.min_filter(filter).mag_filter(filter)
+    }
+
+    pub fn min_filter(mut self, filter: Filter) -> Self {
+        self.sampling.min_filter = filter;
+        self
+    }
+
+    pub fn mag_filter(mut self, filter: Filter) -> Self {
+        self.sampling.mag_filter = filter;
+        self
+    }
+
+    // Generates mipmaps after the upload, sampled with `filter` between
+    // levels
+    pub fn mipmaps(mut self, filter: Filter) -> Self {
+        self.sampling.mipmap_filter = Some(filter);
+        self
+    }
+
+    // Mipmaps are generated when `sampling` has a mipmap filter
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    // `data` holds rows bottom to top without padding, in the layout of
+    // TextureFormat::format and data_type
+    pub fn build(
+        &self,
+        gl: &GL,
+        data: Option<&[u8]>,
+    ) -> Result<Texture, Error> {
+        let expected = self.width as usize
+            * self.height as usize
+            * self.format.bytes_per_pixel();
+        if let Some(data) = data {
+            if data.len() != expected {
+                return Err(Error::DataSize {
+                    expected,
+                    found: data.len(),
+                });
+            }
+        }
+        let (internal_format, format, data_type, _) = self.format.info();
+        unsafe {
+            let texture = gl.create_texture().map_err(Error::ObjectCreation)?;
+            let handle = Rc::new(TextureHandle {
+                gl: gl.clone(),
+                texture,
+            });
+            let restore = Bound::texture(gl, texture);
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                self.width as i32,
+                self.height as i32,
+                0,
+                format,
+                data_type,
+                data,
+            );
+            self.sampling.apply_to_texture(gl);
+            if self.sampling.mipmap_filter.is_some() {
+                gl.generate_mipmap(glow::TEXTURE_2D);
+            }
+            restore.restore(gl);
+            Ok(Texture {
+                texture,
+                w: self.width,
+                h: self.height,
+                format: self.format,
+                mipmapped: self.sampling.mipmap_filter.is_some(),
+                handle,
+            })
+        }
+    }
+}
+
+// How Texture::from_path and from_memory prepare decoded images
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ImageOptions {
+    // multiply color by alpha, for Blend::premultiplied_alpha
+    pub premultiply_alpha: bool,
+    // images are stored top row first, GL textures bottom row first
+    pub flip_y: bool,
+    pub srgb: bool,
+}
+
+// Decoded pixels ready for upload, see ImageOptions::load_path
+pub struct LoadedImage {
+    pub image: RgbaImage,
+    pub format: TextureFormat,
+}
+
+impl LoadedImage {
+    // Uploaded with the default TextureBuilder settings
+    pub fn build(&self, gl: &GL) -> Result<Texture, Error> {
+        self.builder().build(gl, Some(&self.image))
+    }
+
+    pub fn builder(&self) -> TextureBuilder {
+        TextureBuilder::new(self.image.width(), self.image.height())
+            .format(self.format)
+    }
+}
+
+impl ImageOptions {
+    pub fn load_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<LoadedImage, Error> {
+        let path = path.as_ref();
+        let image = image::open(path).map_err(|e| match e {
+            ImageError::IoError(e) => Error::Io {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            },
+            e => Error::Image(e.to_string()),
+        })?;
+        Ok(self.prepare(image))
+    }
+
+    pub fn load_memory(&self, bytes: &[u8]) -> Result<LoadedImage, Error> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| Error::Image(e.to_string()))?;
+        Ok(self.prepare(image))
+    }
+
+    fn prepare(&self, image: DynamicImage) -> LoadedImage {
+        let mut image = image.to_rgba();
+        if self.premultiply_alpha {
+            for pixel in image.pixels_mut() {
+                let alpha = u32::from(pixel[3]);
+                for channel in pixel.data[..3].iter_mut() {
+                    *channel =
+                        ((u32::from(*channel) * alpha + 127) / 255) as u8;
+                }
+            }
+        }
+        if self.flip_y {
+            image = image::imageops::flip_vertical(&image);
+        }
+        let format = if self.srgb {
+            TextureFormat::SRGB8A8
+        } else {
+            TextureFormat::RGBA8
+        };
+        LoadedImage { image, format }
+    }
+}
+
+// Name from a binding query, 0 meaning nothing is bound
+fn object(name: i32) -> Option<c_uint> {
+    match name {
+        0 => None,
+        name => Some(name as c_uint),
+    }
+}
+
+// What an upload replaced: the TEXTURE_2D of the active unit, which may
+// be a sampler's, and the unpack alignment
+struct Bound {
+    texture: Option<c_uint>,
+    unpack_alignment: i32,
+}
+
+impl Bound {
+    // binds `texture` for tightly packed rows
+    unsafe fn texture(gl: &GL, texture: c_uint) -> Bound {
+        let bound = Bound {
+            texture: object(gl.get_parameter_i32(glow::TEXTURE_BINDING_2D)),
+            unpack_alignment: gl.get_parameter_i32(glow::UNPACK_ALIGNMENT),
+        };
+        gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        bound
+    }
+
+    unsafe fn restore(self, gl: &GL) {
+        gl.bind_texture(glow::TEXTURE_2D, self.texture);
+        gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, self.unpack_alignment);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::{Event, Recorder};
+
+    #[test]
+    fn from_rgba8_checks_the_data_size() {
+        let gl = GL::from_backend(Recorder::new());
+        assert!(matches!(
+            Texture::from_rgba8(&gl, 2, 2, &[0; 12]),
+            Err(Error::DataSize {
+                expected: 16,
+                found: 12,
+            })
+        ));
+        assert!(Texture::from_rgba8(&gl, 2, 2, &[0; 16]).is_ok());
+    }
+
+    #[test]
+    fn write_region_restores_binding_and_alignment() {
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        let texture = TextureBuilder::new(4, 4).build(&gl, None).unwrap();
+        // a sampler's texture on the active unit
+        recorder.set_parameter(glow::TEXTURE_BINDING_2D, 7);
+        recorder.set_parameter(glow::UNPACK_ALIGNMENT, 8);
+        recorder.clear();
+        texture.write_region(1, 1, 2, 2, &[0; 16]).unwrap();
+        let events = recorder.events();
+        assert_eq!(
+            events[events.len() - 2..],
+            [
+                Event::BindTexture {
+                    target: glow::TEXTURE_2D,
+                    texture: Some(7),
+                },
+                Event::PixelStoreI32 {
+                    parameter: glow::UNPACK_ALIGNMENT,
+                    value: 8,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn read_pixels_restores_the_read_framebuffer() {
+        let recorder = Recorder::new();
+        let gl = GL::from_backend(recorder.clone());
+        let texture = TextureBuilder::new(2, 2).build(&gl, None).unwrap();
+        recorder.set_parameter(glow::READ_FRAMEBUFFER_BINDING, 3);
+        recorder.clear();
+        texture.read_pixels().unwrap();
+        let events = recorder.events();
+        assert!(events.contains(&Event::BindFramebuffer {
+            target: glow::READ_FRAMEBUFFER,
+            framebuffer: Some(3),
+        }));
+        assert!(events.contains(&Event::PixelStoreI32 {
+            parameter: glow::PACK_ALIGNMENT,
+            value: 4,
+        }));
+    }
+}
@@ -1,4 +1,4 @@
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
 
 extern crate proc_macro;
 extern crate syn;
@@ -21,26 +21,36 @@ fn generate_impl(ast: &syn::DeriveInput) -> quote::Tokens {
     let where_clause = &ast.generics.where_clause;
     let fields_vertex_attrib_pointer =
         generate_vertex_attrib_pointer_calls(&ast.body);
+    let fields_vertex_attribute = generate_vertex_attributes(&ast.body);
 
     let vertex_buffer =
         syn::Ident::from(ident.to_string() + &"Buffer".to_string());
     // let vertex_buffer = syn::Ident::from("VertexBuffer".to_string());
     quote! {
+        impl red::data::VertexLayout for #ident {
+            fn attributes() -> Vec<red::data::VertexAttribute> {
+                vec![#(#fields_vertex_attribute),*]
+            }
+        }
+
         pub struct #vertex_buffer<T> {
             vbo: red::buffer::ArrayBuffer,
             pub vao: red::buffer::VertexArray,
-            pub len: Option<usize>, // a bit ugly
+            pub len: red::buffer::VertexCount,
             _phantom_data: std::marker::PhantomData<T>
         }
 
         impl #vertex_buffer<#ident> {
-            pub fn empty_new(gl: &red::GL) -> Result<#vertex_buffer<#ident>, String> {
+            pub fn empty_new(gl: &red::GL) -> Result<#vertex_buffer<#ident>, red::Error> {
                 let vbo: red::buffer::Buffer<red::buffer::BufferTypeArray>
                     = red::buffer::Buffer::new(&gl)?;
+                let vao = red::buffer::VertexArray::new(gl)?;
+                let len = red::buffer::VertexCount::default();
+                vao.share_vertex_count(len.clone());
                 Ok(#vertex_buffer {
                     vbo: vbo,
-                    vao: red::buffer::VertexArray::new(gl)?,
-                    len: None, // DO SOMETHING WITH THAT
+                    vao: vao,
+                    len: len,
                     _phantom_data: std::marker::PhantomData
                 })
             }
@@ -48,29 +58,32 @@ fn generate_impl(ast: &syn::DeriveInput) -> quote::Tokens {
             pub fn dynamic_draw_data(&mut self, shape: &[#ident]) {
                 self.vbo.bind();
                 self.vbo.dynamic_draw_data(shape);
-                self.len = Some(shape.len());
+                self.len.set(Some(shape.len()));
                 self.vbo.unbind();
             }
 
-            pub fn new(gl: &red::GL, shape: &[#ident]) -> Result<#vertex_buffer<#ident>, String> {
+            pub fn new(gl: &red::GL, shape: &[#ident]) -> Result<#vertex_buffer<#ident>, red::Error> {
                 let vbo: red::buffer::Buffer<red::buffer::BufferTypeArray>
                     = red::buffer::Buffer::new(&gl)?;
                 vbo.bind();
                 vbo.static_draw_data(shape);
                 vbo.unbind();
+                let vao = red::buffer::VertexArray::new(gl)?;
+                let len = red::buffer::VertexCount::new(std::cell::Cell::new(Some(shape.len())));
+                vao.share_vertex_count(len.clone());
                 Ok(#vertex_buffer {
                     vbo: vbo,
-                    vao: red::buffer::VertexArray::new(gl)?,
-                    len: Some(shape.len()),
+                    vao: vao,
+                    len: len,
                     _phantom_data: std::marker::PhantomData
                 })
             }
 
-            pub fn map_array(&self) -> Result<red::buffer::MapArray<#ident>, String>{
+            pub fn map_array(&self) -> Result<red::buffer::MapArray<#ident>, red::Error>{
                 unsafe {
                     self.vbo.bind();
-                    let res: Result<red::buffer::MapArray<#ident>, String> =
-                        red::buffer::MapArray::new(&self.vbo.gl, self.len.unwrap()); // TODO: move gl to vertex buffer
+                    let res: Result<red::buffer::MapArray<#ident>, red::Error> =
+                        red::buffer::MapArray::new(&self.vbo.gl, self.len.get().unwrap()); // TODO: move gl to vertex buffer
                     res
                 }
             }
@@ -79,11 +92,12 @@ fn generate_impl(ast: &syn::DeriveInput) -> quote::Tokens {
 
         impl red::buffer::VertexBufferBehavior for #vertex_buffer<#ident> {
             #[allow(unused_variables)]
-            fn vertex_attrib_pointers(&self, gl: &red::GL, program: &red::shader::Program) {
+            fn vertex_attrib_pointers(&self, gl: &red::GL, program: &red::shader::Program) -> Result<(), red::Error> {
                 let stride = ::std::mem::size_of::<#ident>();
                 let offset = 0;
 
                 #(#fields_vertex_attrib_pointer)*
+                Ok(())
             }
 
             fn bind(&self) {
@@ -94,6 +108,10 @@ fn generate_impl(ast: &syn::DeriveInput) -> quote::Tokens {
                 self.vbo.unbind();
             }
 
+            fn vertex_count(&self) -> red::buffer::VertexCount {
+                self.len.clone()
+            }
+
         }
     }
     // );
@@ -119,6 +137,59 @@ fn generate_vertex_attrib_pointer_calls(
     }
 }
 
+// Value of #[name = "N"] on a field
+fn field_attribute_u32(
+    field: &syn::Field,
+    field_name: &str,
+    name: &str,
+) -> Option<u32> {
+    let attr = field.attrs.iter().find(|a| a.value.name() == name)?;
+    match attr.value {
+        syn::MetaItem::NameValue(_, syn::Lit::Str(ref s, _)) => {
+            Some(s.parse().unwrap_or_else(|_| {
+                panic!(
+                    "Field {} {} attribute value must contain an integer",
+                    field_name, name
+                )
+            }))
+        }
+        _ => panic!(
+            "Field {} {} attribute value must be a string literal",
+            field_name, name
+        ),
+    }
+}
+
+fn generate_vertex_attributes(body: &syn::Body) -> Vec<quote::Tokens> {
+    match body {
+        &syn::Body::Struct(syn::VariantData::Struct(ref s)) => s
+            .iter()
+            .map(|field| {
+                let field_name = match field.ident {
+                    Some(ref i) => format!("{}", i),
+                    None => String::from(""),
+                };
+                let field_ty = &field.ty;
+                let location =
+                    match field_attribute_u32(field, &field_name, "location") {
+                        Some(location) => quote! { Some(#location) },
+                        None => quote! { None },
+                    };
+                quote! {
+                    red::data::VertexAttribute {
+                        name: #field_name,
+                        components: #field_ty::COMPONENTS,
+                        base_type: #field_ty::BASE_TYPE,
+                        location: #location,
+                    }
+                }
+            })
+            .collect(),
+        // the other bodies are rejected in generate_vertex_attrib_pointer_calls
+        _ => vec![],
+    }
+}
+
 fn generate_struct_field_vertex_attrib_pointer_call(
     field: &syn::Field,
 ) -> quote::Tokens {
@@ -127,46 +198,44 @@ fn generate_struct_field_vertex_attrib_pointer_call(
         None => String::from(""),
     };
     let field_ty = &field.ty;
-    let divisor_call = match field
-        .attrs
-        .iter()
-        .filter(|a| a.value.name() == "divisor")
-        .next()
+    let divisor_call = match field_attribute_u32(field, &field_name, "divisor")
     {
-        Some(attr) => {
-            let divisor_value: u32 = match attr.value {
-                syn::MetaItem::NameValue(_, syn::Lit::Str(ref s, _)) => {
-                    s.parse().unwrap_or_else(|_| {
-                        panic!(
-                            "Field {} divisor attribute value must contain an integer",
-                            field_name
-                        )
-                    })
-                }
-                _ => panic!(
-                    "Field {} divisor attribute value must be a string literal",
-                    field_name
-                ),
+        Some(divisor_value) => quote! {
+            gl.vertex_attrib_divisor(location, #divisor_value);
+        },
+        None => quote! {},
+    };
+    // fixed locations are bound by Program::from_shaders_with_layout, other
+    // programs may have put the attribute elsewhere
+    let location = match field_attribute_u32(field, &field_name, "location") {
+        Some(location) => quote! {
+            let location: u32 = #location;
+            let found = unsafe {
+                gl.get_attrib_location(program.id(), &#field_name)
             };
-
-            quote! {
-                gl.vertex_attrib_divisor(location, #divisor_value);
+            if found >= 0 && found as u32 != location {
+                return Err(red::Error::AttributeLocation {
+                    name: #field_name.to_string(),
+                    found: found as u32,
+                    fixed: location,
+                });
             }
-        }
-        None => quote! {},
+        },
+        None => quote! {
+            let location = unsafe {
+                gl.get_attrib_location(program.id(), &#field_name)
+            };
+            if location < 0 {
+                panic!("vertex attribute {} is not found in shader or is not active", #field_name);
+            }
+            let location = location as u32;
+        },
     };
 
     quote! {
-        let location = unsafe {
-            gl.get_attrib_location(program.id(), &#field_name)
-        };
-        if location < 0 {
-            panic!("vertex attribute {} is not found in shader or is not active", #field_name);
-        }
-        let location = location as u32;
+        #location
         unsafe {
             #field_ty::vertex_attrib_pointer(gl, stride as ::std::os::raw::c_int, location, offset as ::std::os::raw::c_int);
-            // #divisor_call
             #divisor_call
         }
         let offset = offset + ::std::mem::size_of::<#field_ty>();